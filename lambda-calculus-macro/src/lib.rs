@@ -1,145 +1,221 @@
-use std::rc::Rc;
+use std::{iter::Peekable, rc::Rc};
 
-use proc_macro;
-use proc_macro2::{token_stream::IntoIter, TokenStream, TokenTree};
+use lambda_calculus::{context::Context, encoding::MAX_NUMERAL, raw_expr::Param};
+use proc_macro2::{token_stream::IntoIter, Span, TokenStream, TokenTree};
 use quote::{quote, quote_spanned};
-use lambda_calculus::{context::Context, raw_expr::Param};
 
+/* Parses a lambda expression at compile time, using the same syntax as the run-time parser.
+ * Identifiers that are not bound by an enclosing lambda refer to Rust values of type `RawExpr`,
+ * which are cloned. If there is no such value, rustc reports its generic "cannot find value in this
+ * scope" error (E0425) at the identifier; this also covers misspelled lambda variables. */
 #[proc_macro]
 pub fn raw_expr(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    parse_stream(input.into(), &Context::Root).into()
+    parse_stream(input.into(), Span::call_site(), &Context::Root)
+        .unwrap_or_else(|err| err)
+        .into()
 }
 
-fn parse_stream(input: TokenStream, context: &Context) -> TokenStream {
-    parse(&mut input.into_iter(), context)
+// The error contains a `compile_error!` invocation that points at the offending token.
+type ParseResult<T> = Result<T, TokenStream>;
+
+// The remaining tokens of a stream, along with the span to report if they end prematurely.
+struct TokenIter {
+    iter: Peekable<IntoIter>,
+    end: Span,
+}
+
+impl TokenIter {
+    fn next(&mut self) -> Option<TokenTree> {
+        self.iter.next()
+    }
+
+    fn peek(&mut self) -> Option<&TokenTree> {
+        self.iter.peek()
+    }
+
+    /* Returns a compile error stating what was expected instead of the given token, pointing at
+     * the token, or at the end of the input if there is none. */
+    fn unexpected<T>(&self, token: Option<&TokenTree>, expected: &str) -> ParseResult<T> {
+        match token {
+            Some(token) => Err(error(
+                token.span(),
+                &format!("expected {expected}, found `{token}`"),
+            )),
+            None => Err(error(
+                self.end,
+                &format!("expected {expected}, found end of input"),
+            )),
+        }
+    }
 }
 
-fn parse(iter: &mut IntoIter, context: &Context) -> TokenStream {
-    if let Some(mut result) = try_parse_one(iter, context) {
-        while let Some(arg) = try_parse_one(iter, context) {
+fn error(span: Span, msg: &str) -> TokenStream {
+    quote_spanned! {
+        span =>
+        compile_error!(#msg)
+    }
+}
+
+// end is the span of the closing delimiter, or of the whole macro invocation at the top level.
+fn parse_stream(input: TokenStream, end: Span, context: &Context) -> ParseResult<TokenStream> {
+    let mut iter = TokenIter {
+        iter: input.into_iter().peekable(),
+        end,
+    };
+    let result = parse(&mut iter, context)?;
+    if let Some(token) = iter.next() {
+        Err(error(token.span(), &format!("unexpected `{token}`")))
+    } else {
+        Ok(result)
+    }
+}
+
+fn parse(iter: &mut TokenIter, context: &Context) -> ParseResult<TokenStream> {
+    if let Some(mut result) = try_parse_one(iter, context)? {
+        while let Some(arg) = try_parse_one(iter, context)? {
             let app = quote! {
                 lambda_calculus::raw_expr::RawExpr::from(lambda_calculus::raw_expr::RawAppExpr {
                     fun: #result,
                     arg: #arg
                 })
             };
-            result = app.into();
+            result = app;
         }
-        result
+        Ok(result)
     } else {
-        quote! {
-            compile_error!("expected expression")
-        }
+        let token = iter.peek().cloned();
+        iter.unexpected(token.as_ref(), "expression")
     }
 }
 
-fn try_parse_one(iter: &mut IntoIter, context: &Context) -> Option<TokenStream> {
-    if let Some(token) = iter.next() {
-        match &token {
-            TokenTree::Group(group) => Some(parse_stream(group.stream(), context)),
-            TokenTree::Ident(ident) => {
-                let mut ident_str = ident.to_string();
-                if let Some(ident_stripped) = ident_str.strip_prefix('λ') {
-                    if ident_stripped.is_empty() {
-                        if let Some(token) = iter.next() {
-                            if let TokenTree::Ident(ident) = &token {
-                                ident_str = ident.to_string();
-                            } else {
-                                return Some(quote_spanned! {
-                                    token.span() =>
-                                    compile_error!("expected identifier")
-                                });
-                            }
-                        } else {
-                            return Some(quote_spanned! {
-                                token.span() =>
-                                compile_error!("expected binder")
-                            });
+fn try_parse_one(iter: &mut TokenIter, context: &Context) -> ParseResult<Option<TokenStream>> {
+    if let Some(TokenTree::Ident(ident)) = iter.peek() {
+        if ident == "in" {
+            // Terminates the value of a let expression.
+            return Ok(None);
+        }
+    }
+    let Some(token) = iter.next() else {
+        return Ok(None);
+    };
+    let expr = match &token {
+        TokenTree::Group(group) => parse_stream(group.stream(), group.span_close(), context)?,
+        TokenTree::Ident(ident) => {
+            let ident_str = ident.to_string();
+            if ident_str == "let" || ident_str == "letrec" {
+                parse_let(iter, context, ident_str == "letrec")?
+            } else if let Some(ident_stripped) = ident_str.strip_prefix('λ') {
+                if ident_stripped.is_empty() {
+                    match iter.next() {
+                        Some(TokenTree::Ident(ident)) => {
+                            parse_binder_content(iter, &ident.to_string(), ident.span(), context)?
                         }
-                    } else {
-                        ident_str = ident_stripped.to_string();
-                    }
-                    let result = try_parse_binder_content(iter, &ident_str, context);
-                    if result.is_some() {
-                        result
-                    } else {
-                        Some(quote_spanned! {
-                            token.span() =>
-                            compile_error!("expected identifier")
-                        })
+                        token => return iter.unexpected(token.as_ref(), "binder"),
                     }
-                } else if let Some(idx) = context.get_var_index(&ident_str) {
-                    Some(quote_spanned! {
-                        token.span() =>
-                        lambda_calculus::raw_expr::RawExpr::Var(#idx)
-                    })
                 } else {
-                    Some(quote_spanned! {token.span() => (#token).clone()})
+                    parse_binder_content(iter, ident_stripped, ident.span(), context)?
                 }
+            } else if let Some(idx) = context.get_var_index(&ident_str) {
+                quote_spanned! {
+                    token.span() =>
+                    lambda_calculus::raw_expr::RawExpr::Var(#idx)
+                }
+            } else {
+                /* Anything else must be a Rust value in scope. Since a proc macro cannot see
+                 * which Rust values are in scope, an identifier that is neither a lambda variable
+                 * nor a Rust value is reported by rustc itself at this token (error E0425,
+                 * "cannot find value in this scope"). */
+                quote_spanned! {token.span() => (#token).clone()}
             }
-            _ => Some(quote_spanned! {
-                token.span() =>
-                compile_error!("expected expression")
-            }),
         }
-    } else {
-        None
-    }
+        TokenTree::Literal(literal) => parse_numeral(&literal.to_string(), &token)?,
+        _ => return iter.unexpected(Some(&token), "expression"),
+    };
+    Ok(Some(expr))
 }
 
-fn try_parse_binder_content(
-    iter: &mut IntoIter,
+/* Parses the rest of a lambda after the binder with the given name, i.e. further binders and the
+ * body. The generated lambda is spanned at the binder. */
+fn parse_binder_content(
+    iter: &mut TokenIter,
     name: &str,
+    span: Span,
     context: &Context,
-) -> Option<TokenStream> {
-    if let Some(token) = iter.next() {
-        let param = Rc::new(Param { name: name.into() });
-        let body_context = Context::Var {
-            param: &param,
-            parent: context,
-        };
-        match &token {
-            TokenTree::Ident(ident) => {
-                let ident_str = ident.to_string();
-                if let Some(body) =
-                    try_parse_binder_content(iter, &ident_str.to_string(), &body_context)
-                {
-                    // TODO: We should probably make use of spans here and at some other places.
-                    Some(quote! {
-                        lambda_calculus::raw_expr::RawExpr::from(lambda_calculus::raw_expr::RawLambdaExpr {
-                            param: std::rc::Rc::new(Param { name: (#name).into() }),
-                            body: #body
-                        })
-                    })
-                } else {
-                    Some(quote_spanned! {
-                        token.span() =>
-                        compile_error!("incomplete binder")
-                    })
-                }
-            }
-            TokenTree::Punct(punct) => {
-                if punct.as_char() == '.' {
-                    let body = parse(iter, &body_context);
-                    Some(quote! {
-                        lambda_calculus::raw_expr::RawExpr::from(lambda_calculus::raw_expr::RawLambdaExpr {
-                            param: std::rc::Rc::new(Param { name: (#name).into() }),
-                            body: #body
-                        })
-                    })
-                } else {
-                    Some(quote_spanned! {
-                        token.span() =>
-                        compile_error!("expected `.` or identifier")
-                    })
-                }
-            }
-            _ => Some(quote_spanned! {
-                token.span() =>
-                compile_error!("expected `.` or identifier")
-            }),
+) -> ParseResult<TokenStream> {
+    let param = Rc::new(Param { name: name.into() });
+    let body_context = Context::Var {
+        param: &param,
+        parent: context,
+    };
+    let body = match iter.next() {
+        Some(TokenTree::Ident(ident)) => {
+            parse_binder_content(iter, &ident.to_string(), ident.span(), &body_context)?
         }
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '.' => parse(iter, &body_context)?,
+        token => return iter.unexpected(token.as_ref(), "`.` or identifier"),
+    };
+    Ok(quote_spanned! {
+        span =>
+        lambda_calculus::raw_expr::RawExpr::from(lambda_calculus::raw_expr::RawLambdaExpr {
+            param: std::rc::Rc::new(lambda_calculus::raw_expr::Param { name: (#name).into() }),
+            body: #body
+        })
+    })
+}
+
+fn parse_let(iter: &mut TokenIter, context: &Context, rec: bool) -> ParseResult<TokenStream> {
+    let (name, span) = match iter.next() {
+        Some(TokenTree::Ident(ident)) => (ident.to_string(), ident.span()),
+        token => return iter.unexpected(token.as_ref(), "identifier"),
+    };
+    match iter.next() {
+        Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => {}
+        token => return iter.unexpected(token.as_ref(), "`=`"),
+    }
+    let param = Rc::new(Param { name: name.clone() });
+    let body_context = Context::Var {
+        param: &param,
+        parent: context,
+    };
+    let value = parse(iter, if rec { &body_context } else { context })?;
+    match iter.next() {
+        Some(TokenTree::Ident(ident)) if ident == "in" => {}
+        token => return iter.unexpected(token.as_ref(), "`in`"),
+    }
+    let body = parse(iter, &body_context)?;
+    let constructor = if rec {
+        quote! { new_letrec }
     } else {
-        None
+        quote! { new_let }
+    };
+    Ok(quote_spanned! {
+        span =>
+        lambda_calculus::raw_expr::RawExpr::#constructor(
+            std::rc::Rc::new(lambda_calculus::raw_expr::Param { name: (#name).into() }),
+            #value,
+            #body
+        )
+    })
+}
+
+// Same syntax as in the run-time parser, but without a configurable default encoding.
+fn parse_numeral(literal: &str, token: &TokenTree) -> ParseResult<TokenStream> {
+    let digits_end = literal
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(literal.len());
+    let constructor = match &literal[digits_end..] {
+        "" | "c" => quote! { church_numeral },
+        "s" => quote! { scott_numeral },
+        _ => return Err(error(token.span(), &format!("invalid numeral `{literal}`"))),
+    };
+    match literal[..digits_end].parse::<u64>() {
+        Ok(n) if n <= MAX_NUMERAL => Ok(quote_spanned! {
+            token.span() =>
+            lambda_calculus::raw_expr::RawExpr::#constructor(#n)
+        }),
+        _ => Err(error(
+            token.span(),
+            &format!("numeral `{literal}` too large (maximum {MAX_NUMERAL})"),
+        )),
     }
 }
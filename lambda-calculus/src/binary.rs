@@ -0,0 +1,165 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{prefix::*, raw_expr::*};
+
+/* Compact binary format of expressions: a version byte, then a table of all parameter names
+ * (count, followed by length and UTF-8 bytes of each name), then the expression in prefix order.
+ * Each subexpression starts with a tag byte, followed by the De Bruijn index for variables, or the
+ * position of the parameter name in the table for lambdas. All numbers are LEB128 varints. */
+
+const FORMAT_VERSION: u8 = 1;
+
+const TAG_VAR: u8 = 0;
+const TAG_APP: u8 = 1;
+const TAG_LAMBDA: u8 = 2;
+
+impl RawExpr {
+    // Fails if the expression contains primitives, which cannot be encoded.
+    pub fn to_binary(&self) -> Result<Vec<u8>, String> {
+        let mut encoder = Encoder {
+            names: Vec::new(),
+            name_indices: HashMap::new(),
+            body: Vec::new(),
+        };
+        encoder.write_expr(self)?;
+        let mut result = vec![FORMAT_VERSION];
+        write_varint(&mut result, encoder.names.len() as u64);
+        for name in encoder.names {
+            write_varint(&mut result, name.len() as u64);
+            result.extend_from_slice(name.as_bytes());
+        }
+        result.extend(encoder.body);
+        Ok(result)
+    }
+
+    /* Decodes an expression produced by to_binary. Besides checking the format, this verifies that
+     * the expression is closed, so the result can be printed safely. */
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, String> {
+        let mut decoder = Decoder {
+            bytes,
+            params: Vec::new(),
+        };
+        let version = decoder.read_byte()?;
+        if version != FORMAT_VERSION {
+            return Err(format!("Unsupported format version: {version}"));
+        }
+        let name_count = decoder.read_varint()?;
+        for _ in 0..name_count {
+            let len = decoder.read_varint()?;
+            let name = decoder.read_bytes(len)?;
+            let name = String::from_utf8(name.into()).map_err(|_| "Invalid parameter name.")?;
+            decoder.params.push(Rc::new(Param { name }));
+        }
+        let expr = decoder.read_expr()?;
+        if !decoder.bytes.is_empty() {
+            return Err("Unexpected data after expression.".into());
+        }
+        Ok(expr)
+    }
+}
+
+struct Encoder<'a> {
+    names: Vec<&'a str>,
+    name_indices: HashMap<&'a str, u64>,
+    body: Vec<u8>,
+}
+
+impl<'a> Encoder<'a> {
+    fn write_expr(&mut self, expr: &'a RawExpr) -> Result<(), String> {
+        for expr in expr.prefix_iter() {
+            match expr {
+                RawExpr::Var(idx) => {
+                    self.body.push(TAG_VAR);
+                    write_varint(&mut self.body, *idx as u64);
+                }
+                RawExpr::App(_) => self.body.push(TAG_APP),
+                RawExpr::Lambda(lambda) => {
+                    let name = lambda.param.name.as_str();
+                    let next_index = self.names.len() as u64;
+                    let name_index = *self.name_indices.entry(name).or_insert_with(|| {
+                        self.names.push(name);
+                        next_index
+                    });
+                    self.body.push(TAG_LAMBDA);
+                    write_varint(&mut self.body, name_index);
+                }
+                RawExpr::Prim(prim) => {
+                    return Err(format!("Primitive {} cannot be encoded.", prim.name));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+
+    // Lambdas with the same parameter name share the same Param.
+    params: Vec<Rc<Param>>,
+}
+
+impl<'a> Decoder<'a> {
+    fn read_expr(&mut self) -> Result<RawExpr, String> {
+        let mut builder = PrefixBuilder::default();
+        loop {
+            let node = match self.read_byte()? {
+                TAG_VAR => PrefixNode::Var(self.read_varint()?),
+                TAG_APP => PrefixNode::App,
+                TAG_LAMBDA => {
+                    let name_index = self.read_varint()?;
+                    let param = usize::try_from(name_index)
+                        .ok()
+                        .and_then(|name_index| self.params.get(name_index))
+                        .ok_or_else(|| format!("Parameter name {name_index} is out of range."))?;
+                    PrefixNode::Lambda(param.clone())
+                }
+                tag => return Err(format!("Invalid tag: {tag}")),
+            };
+            if let Some(expr) = builder.push(node)? {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, String> {
+        let (byte, rest) = self.bytes.split_first().ok_or("Unexpected end of data.")?;
+        self.bytes = rest;
+        Ok(*byte)
+    }
+
+    fn read_bytes(&mut self, len: u64) -> Result<&'a [u8], String> {
+        match usize::try_from(len) {
+            Ok(len) if len <= self.bytes.len() => {
+                let (bytes, rest) = self.bytes.split_at(len);
+                self.bytes = rest;
+                Ok(bytes)
+            }
+            _ => Err("Unexpected end of data.".into()),
+        }
+    }
+
+    fn read_varint(&mut self) -> Result<u64, String> {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_byte()?;
+            if (shift == 63 && byte > 1) || shift > 63 {
+                return Err("Varint too large.".into());
+            }
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+}
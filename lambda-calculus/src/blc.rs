@@ -0,0 +1,126 @@
+use std::rc::Rc;
+
+use crate::{prefix::*, raw_expr::*};
+
+/* John Tromp's Binary Lambda Calculus: `00` followed by the body is a lambda, `01` followed by
+ * function and argument is an application, and `1^n 0` is the variable with De Bruijn index n-1
+ * (i.e. indices start at 1 in BLC). */
+
+impl RawExpr {
+    // Returns the BLC encoding as a string of `0` and `1` characters.
+    pub fn to_blc(&self) -> Result<String, String> {
+        let mut bits = Vec::new();
+        self.write_blc(&mut bits)?;
+        Ok(bits
+            .into_iter()
+            .map(|bit| if bit { '1' } else { '0' })
+            .collect())
+    }
+
+    // Returns the BLC encoding packed into bytes, most significant bit first, padded with zeros.
+    pub fn to_blc_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut bits = Vec::new();
+        self.write_blc(&mut bits)?;
+        Ok(bits
+            .chunks(8)
+            .map(|chunk| {
+                (0..8).fold(0, |byte, i| {
+                    (byte << 1) | (chunk.get(i).copied().unwrap_or(false) as u8)
+                })
+            })
+            .collect())
+    }
+
+    fn write_blc(&self, bits: &mut Vec<bool>) -> Result<(), String> {
+        for expr in self.prefix_iter() {
+            match expr {
+                RawExpr::Var(idx) => {
+                    bits.extend((0..=*idx).map(|_| true));
+                    bits.push(false);
+                }
+                RawExpr::App(_) => bits.extend([false, true]),
+                RawExpr::Lambda(_) => bits.extend([false, false]),
+                RawExpr::Prim(prim) => {
+                    return Err(format!("Primitive {} cannot be encoded.", prim.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /* Parses a closed expression from a string of `0` and `1` characters, ignoring whitespace. The
+     * string must contain exactly one expression. Since BLC has no variable names, all parameters
+     * are named `x`, which is fine because printing renames clashing parameters. Errors refer to De
+     * Bruijn indices starting at 0, as in the rest of this crate. */
+    pub fn from_blc(s: &str) -> Result<Self, String> {
+        let mut bits = Vec::new();
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            match c {
+                '0' => bits.push(false),
+                '1' => bits.push(true),
+                _ => return Err(format!("Expected 0 or 1 instead of: {c}")),
+            }
+        }
+        let mut reader = BlcReader::new(&bits);
+        let expr = reader.read_expr()?;
+        if !reader.bits.is_empty() {
+            return Err("Unexpected bits after expression.".into());
+        }
+        Ok(expr)
+    }
+
+    /* Parses a closed expression from packed bytes as produced by to_blc_bytes. Only zero bits may
+     * follow the expression in its last byte. */
+    pub fn from_blc_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let bits: Vec<bool> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| byte & (1 << i) != 0))
+            .collect();
+        let mut reader = BlcReader::new(&bits);
+        let expr = reader.read_expr()?;
+        if reader.bits.len() >= 8 || reader.bits.contains(&true) {
+            return Err("Unexpected bits after expression.".into());
+        }
+        Ok(expr)
+    }
+}
+
+struct BlcReader<'a> {
+    bits: &'a [bool],
+    param: Rc<Param>,
+}
+
+impl<'a> BlcReader<'a> {
+    fn new(bits: &'a [bool]) -> Self {
+        BlcReader {
+            bits,
+            param: Rc::new(Param { name: "x".into() }),
+        }
+    }
+
+    fn read_expr(&mut self) -> Result<RawExpr, String> {
+        let mut builder = PrefixBuilder::default();
+        loop {
+            let node = if self.read_bit()? {
+                let mut idx = 0;
+                while self.read_bit()? {
+                    idx += 1;
+                }
+                PrefixNode::Var(idx)
+            } else if self.read_bit()? {
+                PrefixNode::App
+            } else {
+                PrefixNode::Lambda(self.param.clone())
+            };
+            if let Some(expr) = builder.push(node)? {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, String> {
+        let (bit, rest) = self.bits.split_first().ok_or("Unexpected end of input.")?;
+        self.bits = rest;
+        Ok(*bit)
+    }
+}
@@ -1,7 +1,7 @@
 use core::fmt;
 use std::str::FromStr;
 
-use crate::{context::*, parse::*, raw_expr::*};
+use crate::{context::*, display::*, env::*, error::*, parse::*, raw_expr::*};
 
 // Allow raw expressions to be printed directly.
 // Warning: will panic if an expression is not closed.
@@ -11,17 +11,63 @@ impl fmt::Display for RawExpr {
     }
 }
 
+impl RawExpr {
+    // Like Display, but with the given options.
+    pub fn display_with(&self, options: DisplayOptions) -> WithContext<'_, &RawExpr> {
+        WithContext::root_with_options(self, options)
+    }
+}
+
 impl FromStr for RawExpr {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut input = ParserInput { s };
-        let expr = RawExpr::parse(&mut input, &Context::Root)?;
-        let rest = input.s;
-        if !rest.is_empty() {
-            return Err(format!(
-                "Expected expression or end of input instead of: {rest}"
-            ));
+        RawExpr::parse_all(ParserInput::new(s))
+    }
+}
+
+impl RawExpr {
+    // Parse a closed expression that may reference the definitions in the given environment.
+    pub fn parse_with_env(s: &str, env: &Environment) -> Result<Self, ParseError> {
+        RawExpr::parse_all(ParserInput::with_env(s, env))
+    }
+
+    /* Parse a definition of the form `name = expr`, taking up the entire input. Returns None if the
+     * input is not a definition. */
+    pub fn parse_definition(mut input: ParserInput) -> Result<Option<(String, Self)>, ParseError> {
+        if let Some(name) = input.try_read_definition_name() {
+            let expr = RawExpr::parse_all(input)?;
+            Ok(Some((name.into(), expr)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Parse a closed expression taking up the entire input.
+    pub fn parse_all(mut input: ParserInput) -> Result<Self, ParseError> {
+        RawExpr::parse_to_end(&mut input)
+    }
+
+    /* Like parse_all, but continues after errors and returns all of them, along with a partial
+     * expression where the erroneous parts are replaced with opaque primitives. */
+    pub fn parse_all_recovering(mut input: ParserInput) -> (Self, Vec<ParseError>) {
+        input.errors = Some(Vec::new());
+        let result = RawExpr::parse_to_end(&mut input);
+        let mut errors = input.errors.unwrap_or_default();
+        let expr = result.unwrap_or_else(|err| {
+            errors.push(err);
+            placeholder("?")
+        });
+        (expr, errors)
+    }
+
+    fn parse_to_end(input: &mut ParserInput) -> Result<Self, ParseError> {
+        let mut expr = RawExpr::parse(input, &Context::Root)?;
+        while !input.s.is_empty() {
+            // When recovering, skip the unexpected token and treat the rest as further arguments.
+            input.recover(input.unexpected(&["expression", "end of input"]))?;
+            input.skip_token();
+            expr = RawExpr::parse_from(input, &Context::Root, Some(expr))?;
         }
         Ok(expr)
     }
@@ -1,4 +1,6 @@
-use crate::raw_expr::*;
+use std::{borrow::Cow, collections::BTreeSet, rc::Rc};
+
+use crate::{display::*, parse::*, primitive::*, raw_expr::*};
 
 #[derive(Clone, Copy, Debug)]
 pub enum Context<'a> {
@@ -10,15 +12,19 @@ pub enum Context<'a> {
 }
 
 impl<'a> Context<'a> {
-    pub fn get_var(&self, mut idx: DeBruijnIndex) -> &'a Param {
+    pub fn get_var(&self, idx: DeBruijnIndex) -> &'a Param {
+        self.try_get_var(idx).expect("invalid De Bruijn index")
+    }
+
+    pub fn try_get_var(&self, mut idx: DeBruijnIndex) -> Option<&'a Param> {
         /* The recursive version is much nicer, but Rust has no tail recursion guarantee? */
         let mut ctx = self;
         loop {
             match ctx {
-                Context::Root => panic!("invalid De Bruijn index"),
+                Context::Root => return None,
                 Context::Var { param, parent } => {
                     if idx == 0 {
-                        return param;
+                        return Some(param);
                     } else {
                         ctx = parent;
                         idx -= 1;
@@ -52,24 +58,42 @@ pub struct WithContext<'a, T> {
     pub obj: T,
     pub parens_for_app: bool,
     pub parens_for_lambda: bool,
+    pub options: DisplayOptions,
+
+    /* Names of all primitives referenced anywhere in the root expression. Determined once per
+     * root, so that get_unique_param_name only needs to search a body for primitives if the
+     * parameter name is among them. */
+    pub primitive_names: Rc<BTreeSet<String>>,
 }
 
-impl<'a, T> WithContext<'a, T> {
-    pub fn root(obj: T) -> Self {
+impl<'a> WithContext<'a, &'a RawExpr> {
+    pub fn root(obj: &'a RawExpr) -> Self {
+        Self::root_with_options(obj, DisplayOptions::default())
+    }
+
+    pub fn root_with_options(obj: &'a RawExpr, options: DisplayOptions) -> Self {
+        let mut primitive_names = BTreeSet::new();
+        obj.collect_primitive_names(&mut primitive_names);
         WithContext {
             context: Context::Root,
             obj,
             parens_for_app: false,
             parens_for_lambda: false,
+            options,
+            primitive_names: Rc::new(primitive_names),
         }
     }
+}
 
+impl<'a, T> WithContext<'a, T> {
     fn propagate<T2>(&self, obj: T2) -> WithContext<'a, T2> {
         WithContext {
             context: self.context,
             obj,
             parens_for_app: self.parens_for_app,
             parens_for_lambda: self.parens_for_lambda,
+            options: self.options,
+            primitive_names: self.primitive_names.clone(),
         }
     }
 }
@@ -87,6 +111,8 @@ impl<'a> WithContext<'a, &RawAppExpr> {
             obj: &self.obj.fun,
             parens_for_app: false,
             parens_for_lambda: true,
+            options: self.options,
+            primitive_names: self.primitive_names.clone(),
         }
     }
 
@@ -96,20 +122,59 @@ impl<'a> WithContext<'a, &RawAppExpr> {
             obj: &self.obj.arg,
             parens_for_app: true,
             parens_for_lambda: true,
+            options: self.options,
+            primitive_names: self.primitive_names.clone(),
         }
     }
 }
 
 impl<'a> WithContext<'a, &RawLambdaExpr> {
-    pub fn get_body(&'a self) -> WithContext<'a, &RawExpr> {
+    pub fn get_body(&'a self) -> WithContext<'a, &'a RawExpr> {
+        self.get_body_with_param(&self.obj.param)
+    }
+
+    // Like get_body, but with a replacement for the parameter, e.g. with a different name.
+    pub fn get_body_with_param(&'a self, param: &'a Param) -> WithContext<'a, &'a RawExpr> {
         WithContext {
             context: Context::Var {
-                param: &self.obj.param,
+                param,
                 parent: &self.context,
             },
             obj: &self.obj.body,
             parens_for_app: true,
             parens_for_lambda: false,
+            options: self.options,
+            primitive_names: self.primitive_names.clone(),
+        }
+    }
+
+    /* Returns a name for the parameter that can be printed and parsed back unambiguously: It must
+     * be a valid variable name, must not be used by any other variable in the context, and must
+     * not be the name of a primitive referenced in the body. If the original name does not
+     * qualify, a number is appended to it (or replaces a number, primes, or subscripts at its
+     * end). */
+    pub fn get_unique_param_name(&self) -> Cow<'_, str> {
+        let name = self.obj.param.name.as_str();
+        let is_usable = |name: &str| {
+            is_valid_name(name)
+                && self.context.get_var_index(name).is_none()
+                && !(self.primitive_names.contains(name)
+                    && self.obj.body.references_primitive(name))
+        };
+        if is_usable(name) {
+            return Cow::Borrowed(name);
+        }
+        let mut base = name.trim_end_matches(|c: char| c.is_ascii_digit() || is_name_suffix(c));
+        if !is_valid_name(base) {
+            base = "x";
+        }
+        let mut suffix = 1;
+        loop {
+            let candidate = format!("{base}{suffix}");
+            if is_usable(&candidate) {
+                return Cow::Owned(candidate);
+            }
+            suffix += 1;
         }
     }
 }
@@ -118,6 +183,7 @@ pub enum ContextExprInfo<'a> {
     Var(WithContext<'a, DeBruijnIndex>),
     App(WithContext<'a, &'a RawAppExpr>),
     Lambda(WithContext<'a, &'a RawLambdaExpr>),
+    Prim(&'a Primitive),
 }
 
 impl<'a> WithContext<'a, &RawExpr> {
@@ -126,6 +192,7 @@ impl<'a> WithContext<'a, &RawExpr> {
             RawExpr::Var(var) => ContextExprInfo::Var(self.propagate(*var)),
             RawExpr::App(app) => ContextExprInfo::App(self.propagate(app)),
             RawExpr::Lambda(lambda) => ContextExprInfo::Lambda(self.propagate(lambda)),
+            RawExpr::Prim(prim) => ContextExprInfo::Prim(prim),
         }
     }
 }
@@ -1,10 +1,48 @@
-use std::fmt;
+use std::{borrow::Cow, fmt};
 
 use crate::{context::*, raw_expr::*};
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LambdaSyntax {
+    // `λx.e`
+    #[default]
+    Unicode,
+
+    // `\x.e`
+    Ascii,
+
+    // `\x -> e`
+    Haskell,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct DisplayOptions {
+    pub syntax: LambdaSyntax,
+
+    /* Print Church numerals, booleans, pairs, and lists as `3`, `true`, `(1, 2)`, and `[1, 2]`.
+     * Since e.g. `λt.λf.f` is both 0 and false, numerals take precedence over booleans and
+     * lists. Pairs and lists are only recognized if their components are printed as encodings
+     * as well, as otherwise e.g. every term of the form `λc.c x` would be a singleton list. */
+    pub recognize_encodings: bool,
+
+    // Print nested lambdas as `λx y.body` instead of `λx.λy.body`.
+    pub collapse_binders: bool,
+
+    // Print lambdas as `λx. body` instead of `λx.body`. Does not apply to Haskell syntax.
+    pub space_after_dot: bool,
+
+    /* Print De Bruijn indices instead of variable names, as in `λ λ 1 0`. All other options
+     * except for parentheses are ignored in this mode, as numerals would be ambiguous. */
+    pub de_bruijn: bool,
+}
+
 impl<'a> fmt::Display for WithContext<'a, DeBruijnIndex> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(&self.get_param().name)
+        if self.options.de_bruijn {
+            write!(f, "{}", self.obj)
+        } else {
+            f.write_str(&self.get_param().name)
+        }
     }
 }
 
@@ -22,22 +60,145 @@ impl<'a> fmt::Display for WithContext<'a, &RawAppExpr> {
 
 impl<'a> fmt::Display for WithContext<'a, &RawLambdaExpr> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let param = &self.obj.param.name;
-        let body = self.get_body();
+        let lambda = match self.options.syntax {
+            LambdaSyntax::Unicode => "λ",
+            LambdaSyntax::Ascii | LambdaSyntax::Haskell => "\\",
+        };
         if self.parens_for_lambda {
-            write!(f, "(λ{param}.{body})")
+            write!(f, "({lambda}")?;
+            self.fmt_binders(f)?;
+            f.write_str(")")
         } else {
-            write!(f, "λ{param}.{body}")
+            f.write_str(lambda)?;
+            self.fmt_binders(f)
+        }
+    }
+}
+
+impl<'a> WithContext<'a, &RawLambdaExpr> {
+    // Prints everything after `λ`.
+    fn fmt_binders(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.options.de_bruijn {
+            let body = WithContext {
+                parens_for_app: false,
+                ..self.get_body()
+            };
+            return write!(f, " {body}");
+        }
+        let renamed_param;
+        let param = match self.get_unique_param_name() {
+            Cow::Borrowed(_) => &*self.obj.param,
+            Cow::Owned(name) => {
+                renamed_param = Param { name };
+                &renamed_param
+            }
+        };
+        f.write_str(&param.name)?;
+        let body = self.get_body_with_param(param);
+        if self.options.collapse_binders
+            && !(self.options.recognize_encodings && is_encoded_value(body.obj))
+        {
+            if let ContextExprInfo::Lambda(lambda) = body.info() {
+                f.write_str(" ")?;
+                return lambda.fmt_binders(f);
+            }
+        }
+        if self.options.syntax == LambdaSyntax::Haskell {
+            write!(f, " -> {body}")
+        } else if self.options.space_after_dot {
+            write!(f, ". {body}")
+        } else {
+            write!(f, ".{body}")
         }
     }
 }
 
 impl<'a> fmt::Display for WithContext<'a, &RawExpr> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.options.recognize_encodings && !self.options.de_bruijn {
+            if let Some(result) = self.try_fmt_encoding(f) {
+                return result;
+            }
+        }
         match self.info() {
             ContextExprInfo::Var(var) => var.fmt(f),
             ContextExprInfo::App(app) => app.fmt(f),
             ContextExprInfo::Lambda(lambda) => lambda.fmt(f),
+            ContextExprInfo::Prim(prim) => f.write_str(&prim.name),
+        }
+    }
+}
+
+impl<'a> WithContext<'a, &RawExpr> {
+    fn try_fmt_encoding(&self, f: &mut fmt::Formatter) -> Option<fmt::Result> {
+        if let Some(n) = self.obj.as_church_numeral() {
+            return Some(write!(f, "{n}"));
+        }
+        if let Some(b) = self.obj.as_church_bool() {
+            return Some(write!(f, "{b}"));
+        }
+        if let ContextExprInfo::Lambda(lambda) = self.info() {
+            let body = lambda.get_body();
+            let pair = self.obj.as_church_pair();
+            if let Some((fst, snd)) =
+                pair.filter(|(fst, snd)| is_encoded_value(fst) && is_encoded_value(snd))
+            {
+                let fst = body.delimited(fst);
+                let snd = body.delimited(snd);
+                return Some(write!(f, "({fst}, {snd})"));
+            }
+            let list = self.obj.as_church_list();
+            if let Some((elements, depth)) = list
+                .filter(|(elements, _)| elements.iter().all(|element| is_encoded_value(element)))
+            {
+                if depth == 1 {
+                    return Some(fmt_list(f, &body, &elements));
+                }
+                if let ContextExprInfo::Lambda(inner_lambda) = body.info() {
+                    return Some(fmt_list(f, &inner_lambda.get_body(), &elements));
+                }
+            }
+        }
+        None
+    }
+
+    // An expression that can be printed without parentheses because it is surrounded by others.
+    fn delimited<'b>(&'b self, obj: &'b RawExpr) -> WithContext<'b, &'b RawExpr> {
+        WithContext {
+            context: self.context,
+            obj,
+            parens_for_app: false,
+            parens_for_lambda: false,
+            options: self.options,
+            primitive_names: self.primitive_names.clone(),
+        }
+    }
+}
+
+// Returns true if try_fmt_encoding prints the expression as an encoding.
+fn is_encoded_value(expr: &RawExpr) -> bool {
+    expr.as_church_numeral().is_some()
+        || expr.as_church_bool().is_some()
+        || expr
+            .as_church_pair()
+            .is_some_and(|(fst, snd)| is_encoded_value(fst) && is_encoded_value(snd))
+        || expr
+            .as_church_list()
+            .is_some_and(|(elements, _)| elements.iter().all(|element| is_encoded_value(element)))
+}
+
+fn fmt_list(
+    f: &mut fmt::Formatter,
+    context: &WithContext<&RawExpr>,
+    elements: &[&RawExpr],
+) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
         }
+        let element = context.delimited(element);
+        write!(f, "{element}")?;
     }
+    f.write_str("]")
 }
@@ -0,0 +1,88 @@
+use std::fmt::Write;
+
+use crate::raw_expr::*;
+
+#[derive(Clone, Copy, Default)]
+pub struct DotOptions {
+    // Add a dashed edge from each variable to the lambda that binds it.
+    pub back_edges: bool,
+}
+
+impl RawExpr {
+    /* Renders the expression as a syntax tree in Graphviz DOT format, with one node per
+     * application, lambda, variable, and primitive. Variables are labeled with both their name and
+     * their De Bruijn index, so that the effect of substitution and shifting is visible. */
+    pub fn to_dot(&self, options: DotOptions) -> String {
+        let mut writer = DotWriter {
+            out: String::from("digraph {\n  node [shape=plaintext];\n"),
+            options,
+            binders: Vec::new(),
+            node_count: 0,
+        };
+        writer.write_expr(self);
+        writer.out.push_str("}\n");
+        writer.out
+    }
+}
+
+struct DotWriter<'a> {
+    out: String,
+    options: DotOptions,
+
+    // Node IDs and parameters of all enclosing lambdas, innermost last.
+    binders: Vec<(usize, &'a Param)>,
+
+    node_count: usize,
+}
+
+impl<'a> DotWriter<'a> {
+    // Writes the nodes and edges of expr, and returns the ID of its root node.
+    fn write_expr(&mut self, expr: &'a RawExpr) -> usize {
+        let id = self.node_count;
+        self.node_count += 1;
+        match expr {
+            RawExpr::Var(idx) => {
+                let binder = self
+                    .binders
+                    .len()
+                    .checked_sub(*idx as usize + 1)
+                    .map(|pos| self.binders[pos]);
+                match binder {
+                    Some((binder_id, param)) => {
+                        self.write_node(id, &format!("{} ({idx})", param.name));
+                        if self.options.back_edges {
+                            writeln!(
+                                self.out,
+                                "  n{id} -> n{binder_id} [style=dashed, constraint=false];"
+                            )
+                            .unwrap();
+                        }
+                    }
+                    // Only possible for expressions that are not closed.
+                    None => self.write_node(id, &format!("{idx}")),
+                }
+            }
+            RawExpr::App(app) => {
+                self.write_node(id, "@");
+                let fun = self.write_expr(&app.fun);
+                let arg = self.write_expr(&app.arg);
+                writeln!(self.out, "  n{id} -> n{fun};").unwrap();
+                writeln!(self.out, "  n{id} -> n{arg};").unwrap();
+            }
+            RawExpr::Lambda(lambda) => {
+                self.write_node(id, &format!("λ{}", lambda.param.name));
+                self.binders.push((id, &lambda.param));
+                let body = self.write_expr(&lambda.body);
+                self.binders.pop();
+                writeln!(self.out, "  n{id} -> n{body};").unwrap();
+            }
+            RawExpr::Prim(prim) => self.write_node(id, &prim.name),
+        }
+        id
+    }
+
+    fn write_node(&mut self, id: usize, label: &str) {
+        let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(self.out, "  n{id} [label=\"{label}\"];").unwrap();
+    }
+}
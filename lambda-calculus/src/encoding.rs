@@ -0,0 +1,352 @@
+use std::rc::Rc;
+
+use crate::raw_expr::*;
+
+/* Recognition of standard Church encodings (see prelude.rs), and the corresponding Scott
+ * encodings where they differ. Since reduction includes eta
+ * reduction, the eta-reduced forms are recognized as well. */
+impl RawExpr {
+    // `λf.λx.f (f (... x))`, or `λf.f` for 1.
+    pub fn as_church_numeral(&self) -> Option<u64> {
+        if let RawExpr::Lambda(f_lambda) = self {
+            match &f_lambda.body {
+                RawExpr::Var(0) => return Some(1),
+                RawExpr::Lambda(x_lambda) => {
+                    let mut n = 0;
+                    let mut body = &x_lambda.body;
+                    loop {
+                        match body {
+                            RawExpr::Var(0) => return Some(n),
+                            RawExpr::App(app) if matches!(app.fun, RawExpr::Var(1)) => {
+                                n += 1;
+                                body = &app.arg;
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    // `λt.λf.t` or `λt.λf.f`.
+    pub fn as_church_bool(&self) -> Option<bool> {
+        if let RawExpr::Lambda(t_lambda) = self {
+            if let RawExpr::Lambda(f_lambda) = &t_lambda.body {
+                match f_lambda.body {
+                    RawExpr::Var(1) => return Some(true),
+                    RawExpr::Var(0) => return Some(false),
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+
+    // `λf.f a1 ... an`; the components live in the context of the lambda body.
+    pub fn as_church_tuple(&self, len: usize) -> Option<Vec<&RawExpr>> {
+        if let RawExpr::Lambda(f_lambda) = self {
+            let mut components = Vec::with_capacity(len);
+            let mut body = &f_lambda.body;
+            while let RawExpr::App(app) = body {
+                if components.len() == len || app.arg.references_var(0) {
+                    return None;
+                }
+                components.push(&app.arg);
+                body = &app.fun;
+            }
+            if matches!(body, RawExpr::Var(0)) && components.len() == len {
+                components.reverse();
+                return Some(components);
+            }
+        }
+        None
+    }
+
+    pub fn as_church_pair(&self) -> Option<(&RawExpr, &RawExpr)> {
+        let components = self.as_church_tuple(2)?;
+        Some((components[0], components[1]))
+    }
+
+    /* `λc.λn.c x1 (c x2 (... n))`, or `λc.c x` for a singleton list. Returns the elements together
+     * with the number of binders they live under. */
+    pub fn as_church_list(&self) -> Option<(Vec<&RawExpr>, DeBruijnIndex)> {
+        if let RawExpr::Lambda(c_lambda) = self {
+            match &c_lambda.body {
+                RawExpr::App(app)
+                    if matches!(app.fun, RawExpr::Var(0)) && !app.arg.references_var(0) =>
+                {
+                    return Some((vec![&app.arg], 1));
+                }
+                RawExpr::Lambda(n_lambda) => {
+                    let mut elements = Vec::new();
+                    let mut body = &n_lambda.body;
+                    loop {
+                        match body {
+                            RawExpr::Var(0) => return Some((elements, 2)),
+                            RawExpr::App(outer_app) => match &outer_app.fun {
+                                RawExpr::App(inner_app)
+                                    if matches!(inner_app.fun, RawExpr::Var(1))
+                                        && !inner_app.arg.references_var(0)
+                                        && !inner_app.arg.references_var(1) =>
+                                {
+                                    elements.push(&inner_app.arg);
+                                    body = &outer_app.arg;
+                                }
+                                _ => break,
+                            },
+                            _ => break,
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/* Conversion of encoded expressions to Rust values. All expressions are expected to be closed and
+ * normalized. Subexpressions are returned as closed expressions. */
+impl RawExpr {
+    pub fn to_bool(&self) -> Result<bool, String> {
+        self.as_church_bool()
+            .ok_or_else(|| "Expression is not a boolean.".into())
+    }
+
+    pub fn to_church_numeral(&self) -> Result<u64, String> {
+        self.as_church_numeral()
+            .ok_or_else(|| "Expression is not a Church numeral.".into())
+    }
+
+    // `λs.λz.s (λs.λz.s (... λs.λz.z))`
+    pub fn to_scott_numeral(&self) -> Result<u64, String> {
+        let mut n = 0;
+        let mut expr = self;
+        while let RawExpr::Lambda(s_lambda) = expr {
+            if let RawExpr::Lambda(z_lambda) = &s_lambda.body {
+                match &z_lambda.body {
+                    RawExpr::Var(0) => return Ok(n),
+                    RawExpr::App(app) if matches!(app.fun, RawExpr::Var(1)) => {
+                        n += 1;
+                        expr = &app.arg;
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            break;
+        }
+        Err("Expression is not a Scott numeral.".into())
+    }
+
+    // Tuples are encoded in the same way in Church and Scott encodings.
+    pub fn to_tuple(&self, len: usize) -> Result<Vec<RawExpr>, String> {
+        if let Some(components) = self.as_church_tuple(len) {
+            components
+                .into_iter()
+                .map(|component| component.unshifted(1))
+                .collect()
+        } else {
+            Err(format!("Expression is not a tuple with {len} components."))
+        }
+    }
+
+    pub fn to_pair(&self) -> Result<(RawExpr, RawExpr), String> {
+        let mut components = self.to_tuple(2)?.into_iter();
+        Ok((components.next().unwrap(), components.next().unwrap()))
+    }
+
+    pub fn to_church_list(&self) -> Result<Vec<RawExpr>, String> {
+        if let Some((elements, depth)) = self.as_church_list() {
+            elements
+                .into_iter()
+                .map(|element| element.unshifted(depth))
+                .collect()
+        } else {
+            Err("Expression is not a Church list.".into())
+        }
+    }
+
+    // `λc.λn.c x1 (λc.λn.c x2 (... λc.λn.n))`
+    pub fn to_scott_list(&self) -> Result<Vec<RawExpr>, String> {
+        let mut elements = Vec::new();
+        let mut depth = 0;
+        let mut expr = self;
+        while let RawExpr::Lambda(c_lambda) = expr {
+            if let RawExpr::Lambda(n_lambda) = &c_lambda.body {
+                depth += 2;
+                match &n_lambda.body {
+                    RawExpr::Var(0) => return Ok(elements),
+                    RawExpr::App(outer_app) => {
+                        if let RawExpr::App(inner_app) = &outer_app.fun {
+                            if matches!(inner_app.fun, RawExpr::Var(1)) {
+                                elements.push(inner_app.arg.unshifted(depth)?);
+                                expr = &outer_app.arg;
+                                continue;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            break;
+        }
+        Err("Expression is not a Scott list.".into())
+    }
+
+    // A list of numerals representing bytes.
+    pub fn to_church_bytes(&self) -> Result<Vec<u8>, String> {
+        self.to_church_list()?
+            .iter()
+            .map(|element| to_byte(element.to_church_numeral()?))
+            .collect()
+    }
+
+    pub fn to_scott_bytes(&self) -> Result<Vec<u8>, String> {
+        self.to_scott_list()?
+            .iter()
+            .map(|element| to_byte(element.to_scott_numeral()?))
+            .collect()
+    }
+
+    // A list of numerals representing the bytes of a UTF-8 string.
+    pub fn to_church_string(&self) -> Result<String, String> {
+        to_string(self.to_church_bytes()?)
+    }
+
+    pub fn to_scott_string(&self) -> Result<String, String> {
+        to_string(self.to_scott_bytes()?)
+    }
+
+    // Moves a subexpression that lives under the given number of binders into the root context.
+    fn unshifted(&self, count: DeBruijnIndex) -> Result<RawExpr, String> {
+        let mut result = self.clone();
+        if result.try_unshift(0, count) {
+            Ok(result)
+        } else {
+            Err("Subexpression references a variable of the encoding.".into())
+        }
+    }
+}
+
+/* The largest numeral accepted by the parser and the raw_expr! macro. Numerals are expanded into
+ * terms whose size and nesting depth are proportional to their value, so larger ones would exhaust
+ * memory or overflow the stack when printed. */
+pub const MAX_NUMERAL: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NumeralEncoding {
+    #[default]
+    Church,
+    Scott,
+}
+
+/* Conversion of Rust values to encoded expressions, inverse to the conversions above. All given
+ * subexpressions are expected to be closed, so they can be inserted without shifting. */
+impl RawExpr {
+    pub fn boolean(b: bool) -> RawExpr {
+        let t = Rc::new(Param { name: "t".into() });
+        let f = Rc::new(Param { name: "f".into() });
+        let body = RawExpr::Var(if b { 1 } else { 0 });
+        lambda(t, lambda(f, body))
+    }
+
+    pub fn church_numeral(n: u64) -> RawExpr {
+        let f = Rc::new(Param { name: "f".into() });
+        let x = Rc::new(Param { name: "x".into() });
+        let mut body = RawExpr::Var(0);
+        for _ in 0..n {
+            body = app(RawExpr::Var(1), body);
+        }
+        lambda(f, lambda(x, body))
+    }
+
+    pub fn scott_numeral(n: u64) -> RawExpr {
+        let s = Rc::new(Param { name: "s".into() });
+        let z = Rc::new(Param { name: "z".into() });
+        let mut result = lambda(s.clone(), lambda(z.clone(), RawExpr::Var(0)));
+        for _ in 0..n {
+            let body = app(RawExpr::Var(1), result);
+            result = lambda(s.clone(), lambda(z.clone(), body));
+        }
+        result
+    }
+
+    pub fn numeral(n: u64, encoding: NumeralEncoding) -> RawExpr {
+        match encoding {
+            NumeralEncoding::Church => RawExpr::church_numeral(n),
+            NumeralEncoding::Scott => RawExpr::scott_numeral(n),
+        }
+    }
+
+    pub fn tuple(components: impl IntoIterator<Item = RawExpr>) -> RawExpr {
+        let f = Rc::new(Param { name: "f".into() });
+        let mut body = RawExpr::Var(0);
+        for component in components {
+            body = app(body, component);
+        }
+        lambda(f, body)
+    }
+
+    pub fn pair(fst: RawExpr, snd: RawExpr) -> RawExpr {
+        RawExpr::tuple([fst, snd])
+    }
+
+    pub fn church_list(elements: impl IntoIterator<Item = RawExpr>) -> RawExpr {
+        let c = Rc::new(Param { name: "c".into() });
+        let n = Rc::new(Param { name: "n".into() });
+        let elements: Vec<RawExpr> = elements.into_iter().collect();
+        let mut body = RawExpr::Var(0);
+        for element in elements.into_iter().rev() {
+            body = app(app(RawExpr::Var(1), element), body);
+        }
+        lambda(c, lambda(n, body))
+    }
+
+    pub fn scott_list(elements: impl IntoIterator<Item = RawExpr>) -> RawExpr {
+        let c = Rc::new(Param { name: "c".into() });
+        let n = Rc::new(Param { name: "n".into() });
+        let elements: Vec<RawExpr> = elements.into_iter().collect();
+        let mut result = lambda(c.clone(), lambda(n.clone(), RawExpr::Var(0)));
+        for element in elements.into_iter().rev() {
+            let body = app(app(RawExpr::Var(1), element), result);
+            result = lambda(c.clone(), lambda(n.clone(), body));
+        }
+        result
+    }
+
+    pub fn church_bytes(bytes: &[u8]) -> RawExpr {
+        RawExpr::church_list(bytes.iter().map(|b| RawExpr::church_numeral(*b as u64)))
+    }
+
+    pub fn scott_bytes(bytes: &[u8]) -> RawExpr {
+        RawExpr::scott_list(bytes.iter().map(|b| RawExpr::scott_numeral(*b as u64)))
+    }
+
+    pub fn church_string(s: &str) -> RawExpr {
+        RawExpr::church_bytes(s.as_bytes())
+    }
+
+    pub fn scott_string(s: &str) -> RawExpr {
+        RawExpr::scott_bytes(s.as_bytes())
+    }
+}
+
+fn lambda(param: Rc<Param>, body: RawExpr) -> RawExpr {
+    RawLambdaExpr { param, body }.into()
+}
+
+fn app(fun: RawExpr, arg: RawExpr) -> RawExpr {
+    RawAppExpr { fun, arg }.into()
+}
+
+fn to_byte(n: u64) -> Result<u8, String> {
+    u8::try_from(n).map_err(|_| format!("Numeral {n} is not a byte."))
+}
+
+fn to_string(bytes: Vec<u8>) -> Result<String, String> {
+    String::from_utf8(bytes).map_err(|err| format!("Invalid UTF-8: {err}"))
+}
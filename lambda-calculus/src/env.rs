@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+
+use crate::raw_expr::*;
+
+/* Named closed expressions that can be referenced by name when parsing. Since they are closed,
+ * they can be inserted at any depth without adjusting De Bruijn indices. */
+#[derive(Clone, Default)]
+pub struct Environment {
+    definitions: BTreeMap<String, RawExpr>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn define(&mut self, name: impl Into<String>, expr: RawExpr) -> Option<RawExpr> {
+        self.definitions.insert(name.into(), expr)
+    }
+
+    pub fn undefine(&mut self, name: &str) -> Option<RawExpr> {
+        self.definitions.remove(name)
+    }
+
+    pub fn register_primitive(
+        &mut self,
+        name: &str,
+        arity: u32,
+        fun: impl Fn(&[&RawExpr]) -> Option<RawExpr> + 'static,
+    ) {
+        self.define(name, RawExpr::primitive(name, arity, fun));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RawExpr> {
+        self.definitions.get(name)
+    }
+
+    // Returns true if the environment contains a qualified name `namespace.name`.
+    pub fn is_namespace(&self, namespace: &str) -> bool {
+        let prefix = format!("{namespace}.");
+        self.definitions
+            .range(prefix.clone()..)
+            .next()
+            .is_some_and(|(name, _)| name.starts_with(&prefix))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &RawExpr)> {
+        self.definitions
+            .iter()
+            .map(|(name, expr)| (name.as_str(), expr))
+    }
+}
@@ -0,0 +1,88 @@
+use core::fmt;
+
+use crate::{encoding::*, raw_expr::*};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    // The input contains a token other than the expected ones.
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    UnknownVariable(String),
+    InvalidNumeral(String),
+    NumeralTooLarge(String),
+    InvalidDeBruijnIndex(String),
+    DeBruijnIndexOutOfRange(DeBruijnIndex),
+
+    // An imported file could not be loaded, for the given reason.
+    ImportFailed(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+
+    // Byte offset of the error in the source.
+    pub offset: usize,
+
+    // Line and column of the error, starting at 1. The column is counted in characters.
+    pub line: usize,
+    pub column: usize,
+
+    /* Descriptions of the tokens that would have been accepted at this position, e.g. `)` or
+     * `expression`. Empty unless kind is UnexpectedToken or UnexpectedEnd. */
+    pub expected: Vec<String>,
+}
+
+impl ParseError {
+    pub fn new(source: &str, offset: usize, kind: ParseErrorKind, expected: Vec<String>) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        ParseError {
+            kind,
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            expected,
+        }
+    }
+
+    // Returns the message followed by the affected line of the source, with a caret below the error.
+    pub fn render(&self, source: &str) -> String {
+        let line = source.split('\n').nth(self.line - 1).unwrap_or("");
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        // Keep tabs so that the caret lines up.
+        let indent: String = line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!("{self}\n{line}\n{indent}^")
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = self.expected.join(" or ");
+        match &self.kind {
+            ParseErrorKind::UnexpectedToken(token) => {
+                write!(f, "Expected {expected} instead of: {token}")?
+            }
+            ParseErrorKind::UnexpectedEnd => write!(f, "Expected {expected} at end of input.")?,
+            ParseErrorKind::UnknownVariable(name) => write!(f, "Variable {name} not found.")?,
+            ParseErrorKind::InvalidNumeral(word) => write!(f, "Invalid numeral: {word}")?,
+            ParseErrorKind::NumeralTooLarge(digits) => {
+                write!(f, "Numeral too large (maximum {MAX_NUMERAL}): {digits}")?
+            }
+            ParseErrorKind::InvalidDeBruijnIndex(word) => {
+                write!(f, "Invalid De Bruijn index: {word}")?
+            }
+            ParseErrorKind::DeBruijnIndexOutOfRange(idx) => {
+                write!(f, "De Bruijn index {idx} is out of range.")?
+            }
+            ParseErrorKind::ImportFailed(reason) => write!(f, "{reason}")?,
+        }
+        write!(f, " (line {}, column {})", self.line, self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
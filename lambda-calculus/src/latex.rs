@@ -0,0 +1,232 @@
+use std::{borrow::Cow, ptr};
+
+use crate::{context::*, raw_expr::*};
+
+impl RawExpr {
+    // Renders the expression as LaTeX math, e.g. `\lambda x.\, x\; y`.
+    pub fn to_latex(&self) -> String {
+        self.to_latex_with_highlight(None)
+    }
+
+    // Like to_latex, but underlines the given subexpression, typically one returned by next_redex.
+    pub fn to_latex_with_highlight(&self, highlight: Option<&RawExpr>) -> String {
+        let mut result = String::new();
+        write_latex(&mut result, &WithContext::root(self), highlight);
+        result
+    }
+
+    /* Like reduce, but records every intermediate expression, and returns the reduction sequence
+     * as a LaTeX `align*` environment. If highlight is true, the redex contracted in each step is
+     * underlined. */
+    pub fn reduce_to_latex(&mut self, limit: &mut u32, highlight: bool) -> String {
+        let mut result = String::from("\\begin{align*}\n");
+        let mut relation = "";
+        loop {
+            // Reduce a copy, so that the contracted redex can be highlighted in the original.
+            let mut next = self.clone();
+            let step = if *limit > 0 { next.reduce_step() } else { None };
+            let redex = step
+                .as_ref()
+                .filter(|_| highlight)
+                .and_then(|(path, _)| self.get_at(path));
+            let expr = self.to_latex_with_highlight(redex);
+            result.push_str(&format!("  {relation}& {expr}"));
+            let Some((_, kind)) = step else {
+                break;
+            };
+            *self = next;
+            *limit -= 1;
+            relation = match kind {
+                ReductionKind::Beta => "\\to_\\beta ",
+                ReductionKind::Eta => "\\to_\\eta ",
+                ReductionKind::Primitive => "\\to_\\delta ",
+            };
+            result.push_str(" \\\\\n");
+        }
+        result.push_str("\n\\end{align*}\n");
+        result
+    }
+}
+
+fn write_latex(out: &mut String, expr: &WithContext<&RawExpr>, highlight: Option<&RawExpr>) {
+    let highlighted = highlight.is_some_and(|highlight| ptr::eq(highlight, expr.obj));
+    if highlighted {
+        out.push_str("\\underline{");
+    }
+    match expr.info() {
+        ContextExprInfo::Var(var) => write_name(out, &var.get_param().name),
+        ContextExprInfo::App(app) => {
+            if app.parens_for_app {
+                out.push('(');
+            }
+            write_latex(out, &app.get_fun(), highlight);
+            out.push_str("\\; ");
+            write_latex(out, &app.get_arg(), highlight);
+            if app.parens_for_app {
+                out.push(')');
+            }
+        }
+        ContextExprInfo::Lambda(lambda) => {
+            if lambda.parens_for_lambda {
+                out.push('(');
+            }
+            let renamed_param;
+            let param = match lambda.get_unique_param_name() {
+                Cow::Borrowed(_) => &*lambda.obj.param,
+                Cow::Owned(name) => {
+                    renamed_param = Param { name };
+                    &renamed_param
+                }
+            };
+            out.push_str("\\lambda ");
+            write_name(out, &param.name);
+            out.push_str(".\\, ");
+            write_latex(out, &lambda.get_body_with_param(param), highlight);
+            if lambda.parens_for_lambda {
+                out.push(')');
+            }
+        }
+        ContextExprInfo::Prim(prim) => {
+            out.push_str("\\textsf{");
+            out.push_str(&escape(&prim.name));
+            out.push('}');
+        }
+    }
+    if highlighted {
+        out.push('}');
+    }
+}
+
+/* Longer names are set as words via \mathit, and trailing digits (also as Unicode subscripts)
+ * become subscripts. Primes are placed before the subscript. Greek letters are written as the
+ * corresponding macros, and other non-ASCII characters via \text. */
+fn write_name(out: &mut String, name: &str) {
+    let without_primes = name.trim_end_matches(['\'', '′', '″', '‴']);
+    let primes = &name[without_primes.len()..];
+    let base = without_primes.trim_end_matches(|c: char| c.is_ascii_digit() || is_subscript(c));
+    let digits = &without_primes[base.len()..];
+    if base.chars().count() == 1 {
+        write_chars(out, base);
+    } else {
+        out.push_str("\\mathit{");
+        write_chars(out, base);
+        out.push('}');
+    }
+    for c in primes.chars() {
+        out.push_str(match c {
+            '″' => "''",
+            '‴' => "'''",
+            _ => "'",
+        });
+    }
+    if !digits.is_empty() {
+        out.push_str("_{");
+        for c in digits.chars() {
+            if is_subscript(c) {
+                out.push(char::from_digit(c as u32 - '₀' as u32, 10).unwrap());
+            } else {
+                out.push(c);
+            }
+        }
+        out.push('}');
+    }
+}
+
+fn write_chars(out: &mut String, s: &str) {
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if let Some(letter) = greek_letter(c) {
+            out.push_str(letter);
+            // Separate the macro name from a following letter.
+            if letter.starts_with('\\') && chars.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                out.push(' ');
+            }
+        } else if c.is_ascii() {
+            out.push_str(&escape(c.encode_utf8(&mut [0; 4])));
+        } else {
+            out.push_str("\\text{");
+            out.push(c);
+            out.push('}');
+        }
+    }
+}
+
+// Greek capital letters that look like Latin ones have no macros in LaTeX.
+fn greek_letter(c: char) -> Option<&'static str> {
+    Some(match c {
+        'α' => "\\alpha",
+        'β' => "\\beta",
+        'γ' => "\\gamma",
+        'δ' => "\\delta",
+        'ε' => "\\varepsilon",
+        'ζ' => "\\zeta",
+        'η' => "\\eta",
+        'θ' => "\\theta",
+        'ι' => "\\iota",
+        'κ' => "\\kappa",
+        'μ' => "\\mu",
+        'ν' => "\\nu",
+        'ξ' => "\\xi",
+        'ο' => "o",
+        'π' => "\\pi",
+        'ρ' => "\\rho",
+        'ς' => "\\varsigma",
+        'σ' => "\\sigma",
+        'τ' => "\\tau",
+        'υ' => "\\upsilon",
+        'φ' => "\\varphi",
+        'χ' => "\\chi",
+        'ψ' => "\\psi",
+        'ω' => "\\omega",
+        'Α' => "A",
+        'Β' => "B",
+        'Γ' => "\\Gamma",
+        'Δ' => "\\Delta",
+        'Ε' => "E",
+        'Ζ' => "Z",
+        'Η' => "H",
+        'Θ' => "\\Theta",
+        'Ι' => "I",
+        'Κ' => "K",
+        'Λ' => "\\Lambda",
+        'Μ' => "M",
+        'Ν' => "N",
+        'Ξ' => "\\Xi",
+        'Ο' => "O",
+        'Π' => "\\Pi",
+        'Ρ' => "P",
+        'Σ' => "\\Sigma",
+        'Τ' => "T",
+        'Υ' => "\\Upsilon",
+        'Φ' => "\\Phi",
+        'Χ' => "X",
+        'Ψ' => "\\Psi",
+        'Ω' => "\\Omega",
+        _ => return None,
+    })
+}
+
+fn is_subscript(c: char) -> bool {
+    ('₀'..='₉').contains(&c)
+}
+
+// Escapes the characters that have a special meaning in LaTeX.
+fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['\\', '{', '}', '$', '&', '#', '^', '_', '%', '~']) {
+        return Cow::Borrowed(s);
+    }
+    let mut result = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => result.push_str("\\textbackslash{}"),
+            '^' => result.push_str("\\textasciicircum{}"),
+            '~' => result.push_str("\\textasciitilde{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                result.push('\\');
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
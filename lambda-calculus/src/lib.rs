@@ -1,5 +1,21 @@
+pub mod binary;
+pub mod blc;
 pub mod closed;
 pub mod context;
 pub mod display;
+pub mod dot;
+pub mod encoding;
+pub mod env;
+pub mod error;
+pub mod latex;
 pub mod parse;
+mod prefix;
+pub mod prelude;
+pub mod primitive;
+pub mod program;
 pub mod raw_expr;
+#[cfg(feature = "serde")]
+pub mod serde_text;
+#[cfg(feature = "serde")]
+mod serde_tree;
+pub mod sugar;
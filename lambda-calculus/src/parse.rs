@@ -1,14 +1,187 @@
 use std::rc::Rc;
 
-use crate::{context::*, raw_expr::*};
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use crate::{context::*, encoding::*, env::*, error::*, program::*, raw_expr::*};
+
+const KEYWORDS: [&str; 4] = ["let", "letrec", "in", "import"];
+
+/* Returns true if the given source is obviously incomplete because it contains unclosed parentheses
+ * or ends within a block comment, so that a REPL can read more lines. */
+pub fn is_incomplete(s: &str) -> bool {
+    let mut input = ParserInput::new(s);
+    let mut depth = 0;
+    loop {
+        if !input.skip_whitespace() {
+            return true;
+        }
+        if input.s.is_empty() {
+            return depth > 0;
+        }
+        if input.try_read_char('(') {
+            depth += 1;
+        } else if input.try_read_char(')') {
+            depth -= 1;
+        } else {
+            input.skip_token();
+        }
+    }
+}
+
+// Primes and subscript digits can only occur at the end of a name.
+pub(crate) fn is_name_suffix(c: char) -> bool {
+    matches!(c, '\'' | '′' | '″' | '‴' | '₀'..='₉')
+}
+
+// Returns true if the given name can be used as a variable name.
+pub fn is_valid_name(name: &str) -> bool {
+    ParserInput::new(name).try_read_var_name() == Some(name)
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ParserOptions {
+    // Encoding of numerals without an explicit `c` or `s` suffix.
+    pub numerals: NumeralEncoding,
+
+    /* Expect De Bruijn indices instead of variable names, as in `λ λ 1 0`. Names can only refer
+     * to definitions in the environment, and numerals are not available in this mode. */
+    pub de_bruijn: bool,
+}
 
 pub struct ParserInput<'a> {
+    // The entire source, used to determine error positions. s must be a suffix of it.
+    pub source: &'a str,
+
+    // The rest of the source that has not been parsed yet.
     pub s: &'a str,
+    pub env: Option<&'a Environment>,
+    pub options: ParserOptions,
+
+    /* If set, the parser does not fail on errors but collects them here, replacing the erroneous
+     * parts of the input with opaque primitives (see placeholder). */
+    pub errors: Option<Vec<ParseError>>,
 }
 
 impl<'a> ParserInput<'a> {
-    fn skip_whitespace(&mut self) {
-        self.s = self.s.trim_start();
+    pub fn new(s: &'a str) -> Self {
+        ParserInput {
+            source: s,
+            s,
+            env: None,
+            options: ParserOptions::default(),
+            errors: None,
+        }
+    }
+
+    pub fn with_env(s: &'a str, env: &'a Environment) -> Self {
+        ParserInput {
+            source: s,
+            s,
+            env: Some(env),
+            options: ParserOptions::default(),
+            errors: None,
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.source.len() - self.s.len()
+    }
+
+    fn error_at(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(self.source, offset, kind, Vec::new())
+    }
+
+    // Returns an error stating that one of the given tokens was expected at the current position.
+    pub(crate) fn unexpected(&self, expected: &[&str]) -> ParseError {
+        let kind = if self.s.is_empty() {
+            ParseErrorKind::UnexpectedEnd
+        } else {
+            let len = match Self::word_len(self.s) {
+                0 => self.s.chars().next().unwrap().len_utf8(),
+                len => len,
+            };
+            ParseErrorKind::UnexpectedToken(self.s[..len].into())
+        };
+        let expected = expected.iter().map(|token| (*token).into()).collect();
+        ParseError::new(self.source, self.offset(), kind, expected)
+    }
+
+    /* Records the error if recovering from errors, and returns it otherwise. Further errors at the
+     * same position are just consequences of the first one, so they are dropped. */
+    pub(crate) fn recover(&mut self, err: ParseError) -> Result<(), ParseError> {
+        if let Some(errors) = &mut self.errors {
+            if errors.last().is_none_or(|last| last.offset != err.offset) {
+                errors.push(err);
+            }
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
+    fn recover_with_placeholder(&mut self, err: ParseError) -> Result<Option<RawExpr>, ParseError> {
+        self.recover(err)?;
+        Ok(Some(placeholder("?")))
+    }
+
+    /* Skips whitespace as well as line comments starting with `--` or `#` and (possibly nested)
+     * block comments enclosed in `{-` and `-}`. Returns false if the input ends within a block
+     * comment. */
+    fn skip_whitespace(&mut self) -> bool {
+        loop {
+            self.s = self.s.trim_start();
+            if self.s.starts_with("--") || self.s.starts_with('#') {
+                let end = self.s.find('\n').unwrap_or(self.s.len());
+                self.s = &self.s[end..];
+            } else if self.try_read_str("{-") {
+                let mut depth = 1;
+                while depth > 0 {
+                    if self.try_read_str("{-") {
+                        depth += 1;
+                    } else if self.try_read_str("-}") {
+                        depth -= 1;
+                    } else if let Some(c) = self.s.chars().next() {
+                        self.s = &self.s[c.len_utf8()..];
+                    } else {
+                        return false;
+                    }
+                }
+            } else {
+                return true;
+            }
+        }
+    }
+
+    // Skips a name, numeral, or other single character.
+    pub(crate) fn skip_token(&mut self) {
+        let len = match Self::word_len(self.s) {
+            0 => self.s.chars().next().map_or(0, char::len_utf8),
+            len => len,
+        };
+        self.s = &self.s[len..];
+    }
+
+    /* After an error, skips everything up to the closing parenthesis of the current level (without
+     * reading it), the next definition, or the end of the input. */
+    fn skip_to_sync_point(&mut self) {
+        let mut depth = 0;
+        loop {
+            self.skip_whitespace();
+            if self.s.is_empty() || self.is_at_definition() {
+                return;
+            }
+            if self.try_read_char('(') {
+                depth += 1;
+            } else if self.s.starts_with(')') {
+                if depth == 0 {
+                    return;
+                }
+                self.skip_token();
+                depth -= 1;
+            } else {
+                self.skip_token();
+            }
+        }
     }
 
     fn try_read_char(&mut self, c: char) -> bool {
@@ -20,89 +193,548 @@ impl<'a> ParserInput<'a> {
         }
     }
 
-    fn read_char(&mut self, c: char) -> Result<(), String> {
-        if self.try_read_char(c) {
-            Ok(())
+    fn try_read_str(&mut self, s: &str) -> bool {
+        if let Some(rest) = self.s.strip_prefix(s) {
+            self.s = rest;
+            true
         } else {
-            let rest = self.s;
-            Err(format!("Expected {c} instead of: {rest}"))
+            false
         }
     }
 
-    fn try_read_name(&mut self) -> Option<&str> {
+    // The separator between binders and body can be either `.` or `->`.
+    fn try_read_binder_separator(&mut self) -> bool {
+        self.try_read_char('.') || self.try_read_str("->")
+    }
+
+    fn try_read_keyword(&mut self, keyword: &str) -> bool {
         let s = self.s;
-        let end = s
-            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
-            .unwrap_or(s.len());
-        if end == 0 {
+        if self.try_read_name() == Some(keyword) {
+            true
+        } else {
+            self.s = s;
+            false
+        }
+    }
+
+    // Reads the `name =` part of a definition, or nothing if the input does not start with one.
+    pub fn try_read_definition_name(&mut self) -> Option<&'a str> {
+        let s = self.s;
+        self.skip_whitespace();
+        if let Some(name) = self.try_read_var_name() {
+            self.skip_whitespace();
+            if self.try_read_char('=') {
+                return Some(name);
+            }
+        }
+        self.s = s;
+        None
+    }
+
+    /* Reads an import statement `import "path"` or `import name`, optionally followed by a list of
+     * names to import without qualification, as in `import lists (map, fold)`. */
+    pub fn try_parse_import(&mut self) -> Result<Option<Import>, ParseError> {
+        self.skip_whitespace();
+        let offset = self.offset();
+        if !self.try_read_keyword("import") {
+            return Ok(None);
+        }
+        self.skip_whitespace();
+        let (path, namespace) = if self.try_read_char('"') {
+            let Some((path, rest)) = self.s.split_once('"') else {
+                self.s = "";
+                return Err(self.unexpected(&["\""]));
+            };
+            self.s = rest;
+            (path.to_owned(), None)
+        } else if let Some(name) = self.try_read_var_name() {
+            (format!("{name}.lam"), Some(name.to_owned()))
+        } else {
+            return Err(self.unexpected(&["file name", "module name"]));
+        };
+        self.skip_whitespace();
+        let names = if self.try_read_char('(') {
+            let mut names = Vec::new();
+            loop {
+                self.skip_whitespace();
+                let name = self
+                    .try_read_var_name()
+                    .ok_or_else(|| self.unexpected(&["name"]))?;
+                names.push(name.to_owned());
+                self.skip_whitespace();
+                if self.try_read_char(')') {
+                    break;
+                }
+                if !self.try_read_char(',') {
+                    return Err(self.unexpected(&[",", ")"]));
+                }
+            }
+            Some(names)
+        } else if namespace.is_some() {
+            Some(Vec::new())
+        } else {
+            None
+        };
+        Ok(Some(Import {
+            path,
+            namespace,
+            names,
+            offset,
+        }))
+    }
+
+    // Reads the `.name` part of a qualified name such as `lists.map`, without surrounding whitespace.
+    fn try_read_qualified_member(&mut self) -> Option<&'a str> {
+        let s = self.s;
+        if self.try_read_char('.') {
+            if let Some(name) = self.try_read_var_name() {
+                return Some(name);
+            }
+        }
+        self.s = s;
+        None
+    }
+
+    pub(crate) fn is_at_definition(&mut self) -> bool {
+        let s = self.s;
+        let result = self.try_read_definition_name().is_some();
+        self.s = s;
+        result
+    }
+
+    // Like try_read_name, but does not accept keywords.
+    fn try_read_var_name(&mut self) -> Option<&'a str> {
+        let s = self.s;
+        let name = self.try_read_name()?;
+        if KEYWORDS.contains(&name) {
+            self.s = s;
+            None
+        } else {
+            Some(name)
+        }
+    }
+
+    /* Names start with a Unicode XID_Start character or `_` (in particular, not with a digit, as
+     * that indicates a numeral). */
+    fn try_read_name(&mut self) -> Option<&'a str> {
+        let s = self.s;
+        let end = Self::word_len(s);
+        if end == 0 || !s.starts_with(|c: char| c == '_' || (c != 'λ' && is_xid_start(c))) {
             None
         } else {
             self.s = &s[end..];
             Some(&s[..end])
         }
     }
+
+    /* Numerals consist of decimal digits, optionally followed by `c` or `s` to select Church or
+     * Scott encoding explicitly. */
+    fn try_read_numeral(&mut self) -> Result<Option<RawExpr>, ParseError> {
+        let s = self.s;
+        let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        if digits_end == 0 {
+            return Ok(None);
+        }
+        let end = Self::word_len(s);
+        let offset = self.offset();
+        // Skip the numeral even if it is invalid, so that the parser can recover.
+        self.s = &s[end..];
+        let encoding = match &s[digits_end..end] {
+            "" => self.options.numerals,
+            "c" => NumeralEncoding::Church,
+            "s" => NumeralEncoding::Scott,
+            _ => {
+                let kind = ParseErrorKind::InvalidNumeral(s[..end].into());
+                return Err(self.error_at(offset, kind));
+            }
+        };
+        let digits = &s[..digits_end];
+        match digits.parse() {
+            Ok(n) if n <= MAX_NUMERAL => Ok(Some(RawExpr::numeral(n, encoding))),
+            _ => {
+                let kind = ParseErrorKind::NumeralTooLarge(digits.into());
+                Err(self.error_at(offset, kind))
+            }
+        }
+    }
+
+    fn try_read_de_bruijn_index(&mut self) -> Result<Option<DeBruijnIndex>, ParseError> {
+        let s = self.s;
+        let end = Self::word_len(s);
+        if !self.options.de_bruijn || !s.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+        let offset = self.offset();
+        let word = &s[..end];
+        self.s = &s[end..];
+        if let Ok(idx) = word.parse() {
+            Ok(Some(idx))
+        } else {
+            let kind = ParseErrorKind::InvalidDeBruijnIndex(word.into());
+            Err(self.error_at(offset, kind))
+        }
+    }
+
+    /* Words, i.e. names and numerals, consist of Unicode XID_Continue characters other than `λ`,
+     * followed by any number of primes and subscript digits, as in `x'` or `x₁`. */
+    fn word_len(s: &str) -> usize {
+        let body_len = s
+            .find(|c: char| c == 'λ' || !is_xid_continue(c))
+            .unwrap_or(s.len());
+        let suffix = &s[body_len..];
+        body_len + suffix.find(|c| !is_name_suffix(c)).unwrap_or(suffix.len())
+    }
 }
 
 impl RawExpr {
-    pub fn parse(input: &mut ParserInput, context: &Context) -> Result<Self, String> {
-        if let Some(mut expr) = Self::try_parse_one(input, context)? {
-            while let Some(arg) = Self::try_parse_one(input, context)? {
-                expr = RawAppExpr { fun: expr, arg }.into();
+    pub fn parse(input: &mut ParserInput, context: &Context) -> Result<Self, ParseError> {
+        Self::parse_from(input, context, None)
+    }
+
+    /* Parses an expression, or further arguments if fun is given. Instead of recursing into
+     * subexpressions, this keeps the constructs being parsed on an explicit stack, so that
+     * arbitrarily deeply nested input can be parsed. */
+    pub(crate) fn parse_from(
+        input: &mut ParserInput,
+        context: &Context,
+        mut fun: Option<Self>,
+    ) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            context,
+            params: Vec::new(),
+            stack: Vec::new(),
+        };
+        loop {
+            if parser.try_parse_one(input, &mut fun)? {
+                continue;
+            }
+            let expr = if let Some(expr) = fun.take() {
+                expr
+            } else {
+                input.recover(input.unexpected(&["expression"]))?;
+                placeholder("?")
+            };
+            // The expression ends here, which completes the innermost construct.
+            let Some((frame, frame_fun)) = parser.stack.pop() else {
+                return Ok(expr);
+            };
+            match frame {
+                Frame::Paren => {
+                    input.skip_whitespace();
+                    if !input.try_read_char(')') {
+                        input.recover(input.unexpected(&["expression", ")"]))?;
+                        input.skip_to_sync_point();
+                        input.try_read_char(')');
+                    }
+                    fun = Some(apply(frame_fun, expr));
+                }
+                Frame::Lambda(param) => {
+                    parser.params.pop();
+                    let lambda = RawLambdaExpr { param, body: expr }.into();
+                    fun = Some(apply(frame_fun, lambda));
+                }
+                Frame::LetValue { param, rec } => {
+                    input.skip_whitespace();
+                    if !input.try_read_keyword("in") {
+                        input.recover(input.unexpected(&["expression", "in"]))?;
+                    }
+                    let frame = Frame::LetBody {
+                        param,
+                        value: expr,
+                        rec,
+                    };
+                    parser.push(frame, frame_fun);
+                }
+                Frame::LetBody { param, value, rec } => {
+                    parser.params.pop();
+                    let let_expr = if rec {
+                        RawExpr::new_letrec(param, value, expr)
+                    } else {
+                        RawExpr::new_let(param, value, expr)
+                    };
+                    fun = Some(apply(frame_fun, let_expr));
+                }
             }
-            Ok(expr)
+        }
+    }
+
+    /* Parses a definition `name = expr`, where expr extends until the start of the next
+     * definition or the end of the input. */
+    pub fn try_parse_definition<'a>(
+        input: &mut ParserInput<'a>,
+    ) -> Result<Option<(&'a str, Self)>, ParseError> {
+        if let Some(name) = input.try_read_definition_name() {
+            let expr = Self::parse(input, &Context::Root)?;
+            Ok(Some((name, expr)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+// A construct whose subexpression is currently being parsed.
+enum Frame {
+    // The closing parenthesis follows the subexpression.
+    Paren,
+    Lambda(Rc<Param>),
+    LetValue {
+        param: Rc<Param>,
+        rec: bool,
+    },
+    LetBody {
+        param: Rc<Param>,
+        value: RawExpr,
+        rec: bool,
+    },
+}
+
+struct Parser<'a, 'b> {
+    context: &'a Context<'b>,
+
+    // Parameters of the enclosing constructs, innermost last, preceding those in context.
+    params: Vec<Rc<Param>>,
+
+    // The constructs being parsed, each with the function it will be applied to, if any.
+    stack: Vec<(Frame, Option<RawExpr>)>,
+}
+
+impl Parser<'_, '_> {
+    fn push(&mut self, frame: Frame, fun: Option<RawExpr>) {
+        match &frame {
+            Frame::Lambda(param)
+            | Frame::LetValue { param, rec: true }
+            | Frame::LetBody {
+                param, rec: false, ..
+            } => self.params.push(param.clone()),
+            _ => {}
+        }
+        self.stack.push((frame, fun));
+    }
+
+    fn get_var_index(&self, name: &str) -> Option<DeBruijnIndex> {
+        let len = self.params.len();
+        if let Some(pos) = self.params.iter().rposition(|param| param.name == name) {
+            Some((len - 1 - pos) as DeBruijnIndex)
         } else {
-            let rest = input.s;
-            Err(format!("Expected expression instead of: {rest}"))
+            let idx = self.context.get_var_index(name)?;
+            Some(idx + len as DeBruijnIndex)
         }
     }
 
-    fn try_parse_one(input: &mut ParserInput, context: &Context) -> Result<Option<Self>, String> {
+    fn is_valid_index(&self, idx: DeBruijnIndex) -> bool {
+        let len = self.params.len();
+        (idx as usize) < len
+            || self
+                .context
+                .try_get_var(idx - len as DeBruijnIndex)
+                .is_some()
+    }
+
+    /* Parses a variable, numeral, etc. and applies fun to it (or sets fun to it if it is None),
+     * or starts parsing a construct with a subexpression, moving fun to the stack. Returns false
+     * if the input does not continue with an expression. */
+    fn try_parse_one(
+        &mut self,
+        input: &mut ParserInput,
+        fun: &mut Option<RawExpr>,
+    ) -> Result<bool, ParseError> {
         input.skip_whitespace();
-        if input.try_read_char('(') {
-            let expr = Self::parse(input, context)?;
-            input.skip_whitespace();
-            input.read_char(')')?;
-            Ok(Some(expr))
+        let offset = input.offset();
+        let expr = if input.is_at_definition() {
+            // The start of the next definition terminates the current expression.
+            return Ok(false);
+        } else if input.try_read_char('(') {
+            self.push(Frame::Paren, fun.take());
+            return Ok(true);
         } else if input.try_read_char('λ') || input.try_read_char('\\') {
-            input.skip_whitespace();
-            let binder = Self::try_parse_binder(input, context)?;
-            if binder.is_some() {
-                Ok(binder)
-            } else {
-                let rest = input.s;
-                Err(format!("Expected variable name instead of: {rest}"))
+            self.parse_binders(input, fun)?;
+            return Ok(true);
+        } else if input.try_read_keyword("let") {
+            return self.parse_let(input, fun, false);
+        } else if input.try_read_keyword("letrec") {
+            return self.parse_let(input, fun, true);
+        } else if let Some(result) = input.try_read_de_bruijn_index().transpose() {
+            match result {
+                Ok(idx) if self.is_valid_index(idx) => RawExpr::Var(idx),
+                Ok(idx) => {
+                    let kind = ParseErrorKind::DeBruijnIndexOutOfRange(idx);
+                    input.recover(input.error_at(offset, kind))?;
+                    placeholder("?")
+                }
+                Err(err) => {
+                    input.recover(err)?;
+                    placeholder("?")
+                }
             }
-        } else if let Some(name) = input.try_read_name() {
-            if let Some(idx) = context.get_var_index(name) {
-                Ok(Some(RawExpr::Var(idx)))
+        } else if let Some(numeral) = input
+            .try_read_numeral()
+            .or_else(|err| input.recover_with_placeholder(err))?
+        {
+            numeral
+        } else if let Some(name) = input.try_read_var_name() {
+            /* In De Bruijn notation, variables are only referenced by index, so names can only
+             * refer to the environment. */
+            let var_index = if input.options.de_bruijn {
+                None
+            } else {
+                self.get_var_index(name)
+            };
+            // A qualified name requires a namespace that is not shadowed by a local variable.
+            let env = input.env;
+            let is_namespace = env.is_some_and(|env| env.is_namespace(name));
+            let member = if is_namespace && var_index.is_none() {
+                input.try_read_qualified_member()
+            } else {
+                None
+            };
+            if let Some(member) = member {
+                let name = format!("{name}.{member}");
+                if let Some(expr) = env.and_then(|env| env.get(&name)) {
+                    expr.clone()
+                } else {
+                    let kind = ParseErrorKind::UnknownVariable(name);
+                    input.recover(input.error_at(offset, kind))?;
+                    placeholder("?")
+                }
+            } else if let Some(idx) = var_index {
+                RawExpr::Var(idx)
+            } else if let Some(expr) = input.env.and_then(|env| env.get(name)) {
+                expr.clone()
             } else {
-                Err(format!("Variable {name} not found."))
+                let kind = ParseErrorKind::UnknownVariable(name.into());
+                input.recover(input.error_at(offset, kind))?;
+                placeholder(name)
             }
         } else {
-            Ok(None)
+            return Ok(false);
+        };
+        *fun = Some(apply(fun.take(), expr));
+        Ok(true)
+    }
+
+    /* Parses the binders of a lambda up to the separator, i.e. `x y.` in `λx y. body`. In De
+     * Bruijn notation, the separator is optional, and since printing renames clashing parameters,
+     * all parameters are simply named `x`. */
+    fn parse_binders(
+        &mut self,
+        input: &mut ParserInput,
+        fun: &mut Option<RawExpr>,
+    ) -> Result<(), ParseError> {
+        input.skip_whitespace();
+        if input.options.de_bruijn {
+            input.try_read_binder_separator();
+            let param = Rc::new(Param { name: "x".into() });
+            self.push(Frame::Lambda(param), fun.take());
+            return Ok(());
         }
+        let mut has_params = false;
+        while let Some(name) = input.try_read_var_name() {
+            let param = Rc::new(Param { name: name.into() });
+            self.push(Frame::Lambda(param), fun.take());
+            input.skip_whitespace();
+            has_params = true;
+        }
+        if !has_params {
+            // Recover by treating the input as a lambda with an unnamed parameter.
+            input.recover(input.unexpected(&["variable name"]))?;
+            let param = Rc::new(Param { name: "?".into() });
+            self.push(Frame::Lambda(param), fun.take());
+            input.try_read_binder_separator();
+        } else if !input.try_read_binder_separator() {
+            input.recover(input.unexpected(&["variable name", ".", "->"]))?;
+        }
+        Ok(())
     }
 
-    fn try_parse_binder(
+    // Parses `x =` in `let x = value in body` or `letrec x = value in body`.
+    fn parse_let(
+        &mut self,
         input: &mut ParserInput,
-        context: &Context,
-    ) -> Result<Option<Self>, String> {
-        if let Some(name) = input.try_read_name() {
+        fun: &mut Option<RawExpr>,
+        rec: bool,
+    ) -> Result<bool, ParseError> {
+        input.skip_whitespace();
+        if let Some(name) = input.try_read_var_name() {
             let param = Rc::new(Param { name: name.into() });
             input.skip_whitespace();
-            let body_context = Context::Var {
-                param: &param,
-                parent: context,
-            };
-            if let Some(body) = Self::try_parse_binder(input, &body_context)? {
-                Ok(Some(RawLambdaExpr { param, body }.into()))
-            } else {
-                input.read_char('.')?;
-                let body = Self::parse(input, &body_context)?;
-                Ok(Some(RawLambdaExpr { param, body }.into()))
+            if !input.try_read_char('=') {
+                input.recover(input.unexpected(&["="]))?;
             }
+            self.push(Frame::LetValue { param, rec }, fun.take());
         } else {
-            Ok(None)
+            input.recover(input.unexpected(&["variable name"]))?;
+            input.skip_to_sync_point();
+            *fun = Some(apply(fun.take(), placeholder("?")));
+        }
+        Ok(true)
+    }
+}
+
+fn apply(fun: Option<RawExpr>, arg: RawExpr) -> RawExpr {
+    if let Some(fun) = fun {
+        RawAppExpr { fun, arg }.into()
+    } else {
+        arg
+    }
+}
+
+/* Opaque stand-in for a part of the input that could not be parsed, e.g. an unknown variable, when
+ * recovering from errors. */
+pub(crate) fn placeholder(name: &str) -> RawExpr {
+    RawExpr::primitive(name, 0, |_| None)
+}
+
+impl Environment {
+    /* Adds all definitions in the given source, in order. Each definition may reference all
+     * previous ones. */
+    pub fn define_all(&mut self, source: &str) -> Result<(), ParseError> {
+        self.define_all_with_errors(source, 0, None, |_, _| ())
+            .map(|_| ())
+    }
+
+    /* Like define_all, but continues after errors and returns all of them. Definitions containing
+     * errors are added in a partial form. */
+    pub fn define_all_recovering(&mut self, source: &str) -> Vec<ParseError> {
+        match self.define_all_with_errors(source, 0, Some(Vec::new()), |_, _| ()) {
+            Ok(errors) => errors.unwrap_or_default(),
+            Err(err) => vec![err],
+        }
+    }
+
+    /* Additionally passes each definition to on_definition. Parsing starts at the given offset in
+     * the source. */
+    pub(crate) fn define_all_with_errors(
+        &mut self,
+        source: &str,
+        mut pos: usize,
+        mut errors: Option<Vec<ParseError>>,
+        mut on_definition: impl FnMut(&str, &RawExpr),
+    ) -> Result<Option<Vec<ParseError>>, ParseError> {
+        loop {
+            let mut input = ParserInput {
+                s: &source[pos..],
+                errors: errors.take(),
+                ..ParserInput::with_env(source, self)
+            };
+            input.skip_whitespace();
+            if input.s.is_empty() {
+                return Ok(input.errors);
+            }
+            let definition = RawExpr::try_parse_definition(&mut input)?;
+            if definition.is_none() {
+                input.recover(input.unexpected(&["definition"]))?;
+                input.skip_token();
+                while !input.s.is_empty() && !input.is_at_definition() {
+                    input.skip_token();
+                }
+            }
+            pos = source.len() - input.s.len();
+            errors = input.errors;
+            if let Some((name, expr)) = definition {
+                on_definition(name, &expr);
+                let name = name.to_owned();
+                self.define(name, expr);
+            }
         }
     }
 }
@@ -0,0 +1,95 @@
+/* Traversal and construction of expressions in prefix order, i.e. each application is followed by
+ * its function and argument, and each lambda by its body. This is the order used by the binary
+ * formats and the structural serialization. Both directions use an explicit stack instead of
+ * recursion, so that arbitrarily deeply nested expressions can be encoded and decoded. */
+
+use std::rc::Rc;
+
+use crate::raw_expr::*;
+
+impl RawExpr {
+    // Iterates over all subexpressions in prefix order, starting with self.
+    pub(crate) fn prefix_iter(&self) -> PrefixIter<'_> {
+        PrefixIter { stack: vec![self] }
+    }
+}
+
+pub(crate) struct PrefixIter<'a> {
+    // Subexpressions that have yet to be visited, the next one last.
+    stack: Vec<&'a RawExpr>,
+}
+
+impl<'a> Iterator for PrefixIter<'a> {
+    type Item = &'a RawExpr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let expr = self.stack.pop()?;
+        match expr {
+            RawExpr::App(app) => {
+                self.stack.push(&app.arg);
+                self.stack.push(&app.fun);
+            }
+            RawExpr::Lambda(lambda) => self.stack.push(&lambda.body),
+            RawExpr::Var(_) | RawExpr::Prim(_) => {}
+        }
+        Some(expr)
+    }
+}
+
+// A subexpression without its children, as read from one of the formats.
+pub(crate) enum PrefixNode {
+    Var(u64),
+    App,
+    Lambda(Rc<Param>),
+}
+
+/* Assembles an expression from its nodes in prefix order. Since the builder keeps track of the
+ * enclosing lambdas, it also checks that each De Bruijn index refers to one of them, so the result
+ * is always closed. */
+#[derive(Default)]
+pub(crate) struct PrefixBuilder {
+    // The applications and lambdas whose children are still incomplete, innermost last.
+    stack: Vec<Frame>,
+
+    // The number of lambdas on the stack, i.e. the number of valid De Bruijn indices.
+    depth: u64,
+}
+
+enum Frame {
+    Fun,
+    Arg(RawExpr),
+    Body(Rc<Param>),
+}
+
+impl PrefixBuilder {
+    // Adds the next node, and returns the expression once it is complete.
+    pub(crate) fn push(&mut self, node: PrefixNode) -> Result<Option<RawExpr>, String> {
+        let mut expr = match node {
+            PrefixNode::Var(idx) if idx < self.depth => RawExpr::Var(idx as DeBruijnIndex),
+            PrefixNode::Var(idx) => return Err(format!("De Bruijn index {idx} is out of range.")),
+            PrefixNode::App => {
+                self.stack.push(Frame::Fun);
+                return Ok(None);
+            }
+            PrefixNode::Lambda(param) => {
+                self.stack.push(Frame::Body(param));
+                self.depth += 1;
+                return Ok(None);
+            }
+        };
+        loop {
+            match self.stack.pop() {
+                None => return Ok(Some(expr)),
+                Some(Frame::Fun) => {
+                    self.stack.push(Frame::Arg(expr));
+                    return Ok(None);
+                }
+                Some(Frame::Arg(fun)) => expr = RawAppExpr { fun, arg: expr }.into(),
+                Some(Frame::Body(param)) => {
+                    self.depth -= 1;
+                    expr = RawLambdaExpr { param, body: expr }.into();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::env::*;
+
+/* Standard Church encodings. Booleans select one of two arguments, numerals n apply a function n
+ * times, pairs apply a function to both components, and lists are represented by their right
+ * fold. */
+pub const PRELUDE_SOURCE: &str = "
+id = λx. x
+const = λx y. x
+
+true = λt f. t
+false = λt f. f
+if = λb t f. b t f
+and = λp q. p q p
+or = λp q. p p q
+not = λp t f. p f t
+
+zero = λf x. x
+succ = λn f x. f (n f x)
+iszero = λn. n (λx. false) true
+add = λm n f x. m f (n f x)
+mul = λm n f. m (n f)
+pow = λb e. e b
+pred = λn f x. n (λg h. h (g f)) (λu. x) (λu. u)
+sub = λm n. n pred m
+leq = λm n. iszero (sub m n)
+eq = λm n. and (leq m n) (leq n m)
+
+pair = λa b f. f a b
+fst = λp. p true
+snd = λp. p false
+
+nil = λc n. n
+cons = λh t c n. c h (t c n)
+isnil = λl. l (λh t. false) true
+head = λl. l (λh t. h) nil
+tail = λl c n. l (λh t g. g h (t c)) (λt. n) (λh t. t)
+fold = λf z l. l f z
+map = λf l c n. l (λh t. c (f h) t) n
+length = λl. l (λh t. succ t) zero
+
+Y = λf. (λx. f (x x)) (λx. f (x x))
+Z = λf. (λx. f (λv. x x v)) (λx. f (λv. x x v))
+";
+
+impl Environment {
+    pub fn prelude() -> Self {
+        let mut env = Environment::new();
+        env.define_all(PRELUDE_SOURCE)
+            .expect("prelude should be well-formed");
+        env
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+use crate::raw_expr::*;
+
+pub type PrimitiveFn = dyn Fn(&[&RawExpr]) -> Option<RawExpr>;
+
+/* A function implemented in Rust that can be called from lambda terms. When reducing an
+ * application of a primitive to `arity` arguments, the arguments are reduced first, and then the
+ * function is called with them. The arguments and the result live in the context of the
+ * application. If the function returns None, the application is left as-is. */
+pub struct Primitive {
+    pub name: String,
+    pub arity: u32,
+    fun: Box<PrimitiveFn>,
+}
+
+impl Primitive {
+    pub fn new(
+        name: impl Into<String>,
+        arity: u32,
+        fun: impl Fn(&[&RawExpr]) -> Option<RawExpr> + 'static,
+    ) -> Self {
+        Primitive {
+            name: name.into(),
+            arity,
+            fun: Box::new(fun),
+        }
+    }
+
+    pub fn call(&self, args: &[&RawExpr]) -> Option<RawExpr> {
+        (self.fun)(args)
+    }
+}
+
+impl fmt::Debug for Primitive {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Primitive")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{env::*, error::*, parse::*, raw_expr::*};
+
+/* A source file consisting of import statements followed by definitions `name = expr`, each of
+ * which may reference the imported definitions and the previous ones. A definition of `main`
+ * (typically the last one) is the expression to evaluate when running the program. Comments are
+ * allowed as usual. */
+#[derive(Clone, Default)]
+pub struct Program {
+    pub imports: Vec<Import>,
+
+    // All definitions except main, in source order.
+    pub definitions: Vec<(String, RawExpr)>,
+
+    pub main: Option<RawExpr>,
+}
+
+/* `import "path"` imports all definitions of the given file. `import name` imports the file
+ * `name.lam` into a namespace, so that its definitions are available as `name.def`. Both can be
+ * followed by a list of names to import without qualification, as in `import lists (map, fold)`. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Import {
+    // Path of the imported file, relative to the directory of the importing file.
+    pub path: String,
+
+    pub namespace: Option<String>,
+
+    // The definitions to import without qualification, or None for all of them.
+    pub names: Option<Vec<String>>,
+
+    // Byte offset of the import statement in the source.
+    pub offset: usize,
+}
+
+impl Program {
+    /* Parses a program whose definitions may additionally reference the definitions in the given
+     * environment, e.g. the prelude. Imports are not resolved, so the environment must already
+     * contain the imported definitions; see load. */
+    pub fn parse(source: &str, env: &Environment) -> Result<Self, ParseError> {
+        Program::parse_with_errors(source, env, None).map(|(program, _)| program)
+    }
+
+    /* Like parse, but continues after errors in definitions and returns all of them. Definitions
+     * containing errors are included in a partial form. */
+    pub fn parse_recovering(source: &str, env: &Environment) -> (Self, Vec<ParseError>) {
+        match Program::parse_with_errors(source, env, Some(Vec::new())) {
+            Ok((program, errors)) => (program, errors.unwrap_or_default()),
+            Err(err) => (Program::default(), vec![err]),
+        }
+    }
+
+    fn parse_with_errors(
+        source: &str,
+        env: &Environment,
+        errors: Option<Vec<ParseError>>,
+    ) -> Result<(Self, Option<Vec<ParseError>>), ParseError> {
+        let mut program = Program::default();
+        let mut input = ParserInput::new(source);
+        while let Some(import) = input.try_parse_import()? {
+            program.imports.push(import);
+        }
+        let start = source.len() - input.s.len();
+        let errors = env
+            .clone()
+            .define_all_with_errors(source, start, errors, |name, expr| {
+                if name == "main" {
+                    program.main = Some(expr.clone());
+                } else {
+                    program.definitions.push((name.into(), expr.clone()));
+                }
+            })?;
+        Ok((program, errors))
+    }
+
+    /* Reads and parses the given file, after loading all files it imports (recursively). All parse
+     * errors in a file are reported together, each rendered with the path and the affected line. */
+    pub fn load(path: impl AsRef<Path>, env: &Environment) -> Result<Self, String> {
+        let mut loader = Loader {
+            env,
+            stack: Vec::new(),
+            loaded: HashMap::new(),
+        };
+        loader.load(path.as_ref())
+    }
+
+    // Adds all definitions (except main) to the given environment.
+    pub fn define_in(&self, env: &mut Environment) {
+        for (name, expr) in &self.definitions {
+            env.define(name.clone(), expr.clone());
+        }
+    }
+}
+
+struct Loader<'a> {
+    env: &'a Environment,
+
+    // Canonical paths of the files currently being loaded, to detect import cycles.
+    stack: Vec<PathBuf>,
+
+    // Files that have already been loaded, so that each file is only loaded once.
+    loaded: HashMap<PathBuf, Program>,
+}
+
+impl Loader<'_> {
+    fn load(&mut self, path: &Path) -> Result<Program, String> {
+        let read_error = |err| format!("Cannot read {}: {err}", path.display());
+        let source = fs::read_to_string(path).map_err(read_error)?;
+        self.stack.push(path.canonicalize().map_err(read_error)?);
+        let mut env = self.env.clone();
+        let mut input = ParserInput::new(&source);
+        while let Some(import) = input
+            .try_parse_import()
+            .map_err(|err| render(path, &source, err))?
+        {
+            self.import(path, &source, &import, &mut env)?;
+        }
+        let (program, errors) = Program::parse_recovering(&source, &env);
+        if !errors.is_empty() {
+            return Err(errors
+                .into_iter()
+                .map(|err| render(path, &source, err))
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+        self.stack.pop();
+        Ok(program)
+    }
+
+    // Loads the file imported by the given import statement, and adds its definitions to env.
+    fn import(
+        &mut self,
+        path: &Path,
+        source: &str,
+        import: &Import,
+        env: &mut Environment,
+    ) -> Result<(), String> {
+        let error = |kind| {
+            let err = ParseError::new(source, import.offset, kind, Vec::new());
+            render(path, source, err)
+        };
+        let import_path = path.parent().unwrap_or(Path::new("")).join(&import.path);
+        let canonical = import_path.canonicalize().map_err(|err| {
+            let reason = format!("Cannot import {}: {err}", import.path);
+            error(ParseErrorKind::ImportFailed(reason))
+        })?;
+        let program = if let Some(program) = self.loaded.get(&canonical) {
+            program.clone()
+        } else {
+            if let Some(pos) = self.stack.iter().position(|path| *path == canonical) {
+                let cycle: Vec<String> = self.stack[pos..]
+                    .iter()
+                    .chain([&canonical])
+                    .map(|path| path.display().to_string())
+                    .collect();
+                let reason = format!("Import cycle: {}", cycle.join(" -> "));
+                return Err(error(ParseErrorKind::ImportFailed(reason)));
+            }
+            let program = self.load(&import_path)?;
+            self.loaded.insert(canonical, program.clone());
+            program
+        };
+        if let Some(namespace) = &import.namespace {
+            for (name, expr) in &program.definitions {
+                env.define(format!("{namespace}.{name}"), expr.clone());
+            }
+        }
+        match &import.names {
+            None => program.define_in(env),
+            Some(names) => {
+                for name in names {
+                    // Later definitions shadow earlier ones of the same name.
+                    let (_, expr) = program
+                        .definitions
+                        .iter()
+                        .rev()
+                        .find(|(def_name, _)| def_name == name)
+                        .ok_or_else(|| error(ParseErrorKind::UnknownVariable(name.clone())))?;
+                    env.define(name.clone(), expr.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn render(path: &Path, source: &str, err: ParseError) -> String {
+    format!("{}: {}", path.display(), err.render(source))
+}
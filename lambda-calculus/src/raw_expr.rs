@@ -1,22 +1,55 @@
-use std::{mem::take, rc::Rc};
+use std::{collections::BTreeSet, mem::take, rc::Rc};
+
+use crate::primitive::*;
 
 pub type DeBruijnIndex = u32;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Param {
     pub name: String,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReductionKind {
+    Beta,
+    Eta,
+    Primitive,
+}
+
+// Identifies a child of an application or lambda, as a step in the path to a subexpression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Child {
+    Fun,
+    Arg,
+    Body,
+}
+
+/* With the serde feature, expressions are serialized structurally, i.e. as a flat list of nodes
+ * with De Bruijn indices and the original parameter names (see serde_tree.rs). Primitives cannot
+ * be serialized. See serde_text.rs for serialization via the text syntax instead. */
 #[derive(Clone)]
 pub enum RawExpr {
     Var(DeBruijnIndex),
     App(Box<RawAppExpr>),
     Lambda(Box<RawLambdaExpr>),
+    Prim(Rc<Primitive>),
 }
 
 impl RawExpr {
+    pub fn primitive(
+        name: impl Into<String>,
+        arity: u32,
+        fun: impl Fn(&[&RawExpr]) -> Option<RawExpr> + 'static,
+    ) -> Self {
+        RawExpr::Prim(Rc::new(Primitive::new(name, arity, fun)))
+    }
+
     /* Reduce the expression as much as possible, using at most the given number of steps.
-     * Returns true if some reduction was performed. */
+     * Returns true if some reduction was performed.
+     * The leftmost outermost redex is contracted first (normal order), so that arguments are
+     * substituted unreduced. This guarantees that the normal form is found if it exists, which
+     * matters e.g. for recursion via fixed-point combinators. */
     pub fn reduce(&mut self, limit: &mut u32) -> bool {
         let mut reduced = false;
         while *limit > 0 {
@@ -29,8 +62,15 @@ impl RawExpr {
                         *self = beta_red;
                         reduced = true;
                         *limit -= 1;
-                    } else if app.fun.reduce(limit) || app.arg.reduce(limit) {
+                    } else if app.fun.reduce_head(limit)
+                        || app.fun.reduce(limit)
+                        || app.arg.reduce(limit)
+                    {
                         reduced = true;
+                    } else if let Some(prim_result) = app.try_get_primitive_result() {
+                        *self = prim_result;
+                        reduced = true;
+                        *limit -= 1;
                     } else {
                         break;
                     }
@@ -46,11 +86,166 @@ impl RawExpr {
                         break;
                     }
                 }
+                RawExpr::Prim(prim) => {
+                    if prim.arity == 0 {
+                        if let Some(prim_result) = prim.call(&[]) {
+                            *self = prim_result;
+                            reduced = true;
+                            *limit -= 1;
+                            continue;
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        reduced
+    }
+
+    /* Reduces the expression to weak head normal form, i.e. only contracts the beta redex at the
+     * head of the application (if any), repeatedly. */
+    fn reduce_head(&mut self, limit: &mut u32) -> bool {
+        let mut reduced = false;
+        while *limit > 0 {
+            let RawExpr::App(app) = self else {
+                break;
+            };
+            if let Some(beta_red) = app.try_get_beta_reduced() {
+                *self = beta_red;
+                reduced = true;
+                *limit -= 1;
+            } else if app.fun.reduce_head(limit) {
+                reduced = true;
+            } else {
+                break;
             }
         }
         reduced
     }
 
+    // Returns the redex that reduce_head will contract next.
+    fn next_head_redex(&self) -> Option<&RawExpr> {
+        match self {
+            RawExpr::App(app) => match app.fun {
+                RawExpr::Lambda(_) => Some(self),
+                _ => app.fun.next_head_redex(),
+            },
+            _ => None,
+        }
+    }
+
+    /* Performs a single step of reduce, i.e. contracts the leftmost outermost redex. Returns the
+     * path from self to the contracted redex, along with the kind of reduction, or None if the
+     * expression cannot be reduced further. */
+    pub fn reduce_step(&mut self) -> Option<(Vec<Child>, ReductionKind)> {
+        let mut path = Vec::new();
+        let kind = self.step(&mut path)?;
+        path.reverse();
+        Some((path, kind))
+    }
+
+    // Contracts the redex that reduce would contract first, pushing its path in reverse order.
+    fn step(&mut self, path: &mut Vec<Child>) -> Option<ReductionKind> {
+        match self {
+            RawExpr::Var(_) => None,
+            RawExpr::App(app) => {
+                if let Some(beta_red) = app.try_get_beta_reduced() {
+                    *self = beta_red;
+                    Some(ReductionKind::Beta)
+                } else if let Some(kind) = app.fun.head_step(path).or_else(|| app.fun.step(path)) {
+                    path.push(Child::Fun);
+                    Some(kind)
+                } else if let Some(kind) = app.arg.step(path) {
+                    path.push(Child::Arg);
+                    Some(kind)
+                } else {
+                    *self = app.try_get_primitive_result()?;
+                    Some(ReductionKind::Primitive)
+                }
+            }
+            RawExpr::Lambda(lambda) => {
+                if let Some(eta_red) = lambda.try_get_eta_reduced() {
+                    *self = eta_red;
+                    Some(ReductionKind::Eta)
+                } else {
+                    let kind = lambda.body.step(path)?;
+                    path.push(Child::Body);
+                    Some(kind)
+                }
+            }
+            RawExpr::Prim(prim) => {
+                if prim.arity == 0 {
+                    *self = prim.call(&[])?;
+                    Some(ReductionKind::Primitive)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    // Contracts the redex that reduce_head would contract first, pushing its path in reverse order.
+    fn head_step(&mut self, path: &mut Vec<Child>) -> Option<ReductionKind> {
+        let RawExpr::App(app) = self else {
+            return None;
+        };
+        if let Some(beta_red) = app.try_get_beta_reduced() {
+            *self = beta_red;
+            Some(ReductionKind::Beta)
+        } else {
+            let kind = app.fun.head_step(path)?;
+            path.push(Child::Fun);
+            Some(kind)
+        }
+    }
+
+    // Returns the subexpression at the given path, e.g. one returned by reduce_step.
+    pub fn get_at(&self, path: &[Child]) -> Option<&RawExpr> {
+        path.iter()
+            .try_fold(self, |expr, child| match (expr, child) {
+                (RawExpr::App(app), Child::Fun) => Some(&app.fun),
+                (RawExpr::App(app), Child::Arg) => Some(&app.arg),
+                (RawExpr::Lambda(lambda), Child::Body) => Some(&lambda.body),
+                _ => None,
+            })
+    }
+
+    /* Returns the subexpression that the next step of reduce will contract, along with the kind of
+     * reduction. Like reduce, this calls primitives to determine whether they return a result, so
+     * reduce_step should be preferred if the expression is going to be reduced anyway. */
+    pub fn next_redex(&self) -> Option<(&RawExpr, ReductionKind)> {
+        match self {
+            RawExpr::Var(_) => None,
+            RawExpr::App(app) => {
+                if let Some(redex) = self.next_head_redex() {
+                    Some((redex, ReductionKind::Beta))
+                } else if let Some(redex) = app.fun.next_redex() {
+                    Some(redex)
+                } else if let Some(redex) = app.arg.next_redex() {
+                    Some(redex)
+                } else if app.try_get_primitive_result().is_some() {
+                    Some((self, ReductionKind::Primitive))
+                } else {
+                    None
+                }
+            }
+            RawExpr::Lambda(lambda) => {
+                if lambda.is_eta_reducible() {
+                    Some((self, ReductionKind::Eta))
+                } else {
+                    lambda.body.next_redex()
+                }
+            }
+            RawExpr::Prim(prim) => {
+                if prim.arity == 0 && prim.call(&[]).is_some() {
+                    Some((self, ReductionKind::Primitive))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
     /* Substitute the variable with the given De Bruijn index with the given expression, adjusting
      * indices as required. The value is assumed to live in the (idx + 1)th parent context of self.
      * The result is an expression where the specific De Bruijn index is eliminated. */
@@ -70,6 +265,7 @@ impl RawExpr {
             }
             RawExpr::App(app) => app.substitute(idx, value, may_take_value),
             RawExpr::Lambda(lambda) => lambda.substitute(idx, value, may_take_value),
+            RawExpr::Prim(_) => {}
         }
     }
 
@@ -82,6 +278,7 @@ impl RawExpr {
             RawExpr::Var(var) => RawExpr::Var(if *var >= start { var + count } else { *var }),
             RawExpr::App(app) => app.shifted(start, count).into(),
             RawExpr::Lambda(lambda) => lambda.shifted(start, count).into(),
+            RawExpr::Prim(prim) => RawExpr::Prim(prim.clone()),
         }
     }
 
@@ -95,13 +292,49 @@ impl RawExpr {
             }
             RawExpr::App(app) => app.shift(start, count),
             RawExpr::Lambda(lambda) => lambda.shift(start, count),
+            RawExpr::Prim(_) => {}
+        }
+    }
+
+    // Returns true if the variable with the given De Bruijn index occurs in the expression.
+    pub fn references_var(&self, idx: DeBruijnIndex) -> bool {
+        match self {
+            RawExpr::Var(var) => *var == idx,
+            RawExpr::App(app) => app.fun.references_var(idx) || app.arg.references_var(idx),
+            RawExpr::Lambda(lambda) => lambda.body.references_var(idx + 1),
+            RawExpr::Prim(_) => false,
+        }
+    }
+
+    pub fn references_primitive(&self, name: &str) -> bool {
+        match self {
+            RawExpr::Var(_) => false,
+            RawExpr::App(app) => {
+                app.fun.references_primitive(name) || app.arg.references_primitive(name)
+            }
+            RawExpr::Lambda(lambda) => lambda.body.references_primitive(name),
+            RawExpr::Prim(prim) => prim.name == name,
+        }
+    }
+
+    pub fn collect_primitive_names(&self, names: &mut BTreeSet<String>) {
+        match self {
+            RawExpr::Var(_) => {}
+            RawExpr::App(app) => {
+                app.fun.collect_primitive_names(names);
+                app.arg.collect_primitive_names(names);
+            }
+            RawExpr::Lambda(lambda) => lambda.body.collect_primitive_names(names),
+            RawExpr::Prim(prim) => {
+                names.insert(prim.name.clone());
+            }
         }
     }
 
     /* Reverse of shift: If the expression does not reference any of the variables in the given
      * range of De Bruijn indices, eliminite these indices and return true, otherwise do nothing and
      * return false. */
-    fn try_unshift(&mut self, start: DeBruijnIndex, count: DeBruijnIndex) -> bool {
+    pub(crate) fn try_unshift(&mut self, start: DeBruijnIndex, count: DeBruijnIndex) -> bool {
         match self {
             RawExpr::Var(var) => {
                 if *var >= start + count {
@@ -113,6 +346,87 @@ impl RawExpr {
             }
             RawExpr::App(app) => app.try_unshift(start, count),
             RawExpr::Lambda(lambda) => lambda.try_unshift(start, count),
+            RawExpr::Prim(_) => true,
+        }
+    }
+}
+
+/* Dropping an expression recursively would overflow the stack for deeply nested expressions, so
+ * nested subexpressions are detached and dropped in a loop instead. The stack is only needed for
+ * applications where both children are nested. */
+impl Drop for RawExpr {
+    #[inline]
+    fn drop(&mut self) {
+        if self.has_nested_children() {
+            self.drop_children();
+        }
+    }
+}
+
+impl RawExpr {
+    /* Because of Drop, the children of an expression cannot be moved out of it by pattern matching.
+     * Instead, this returns the application by value, or the expression itself if it is not an
+     * application. */
+    pub fn into_app(mut self) -> Result<RawAppExpr, RawExpr> {
+        match &mut self {
+            RawExpr::App(app) => Ok(RawAppExpr {
+                fun: take(&mut app.fun),
+                arg: take(&mut app.arg),
+            }),
+            _ => Err(self),
+        }
+    }
+
+    // Like into_app, but for lambdas.
+    pub fn into_lambda(mut self) -> Result<RawLambdaExpr, RawExpr> {
+        match &mut self {
+            RawExpr::Lambda(lambda) => Ok(RawLambdaExpr {
+                param: lambda.param.clone(),
+                body: take(&mut lambda.body),
+            }),
+            _ => Err(self),
+        }
+    }
+
+    #[inline]
+    fn is_nested(&self) -> bool {
+        matches!(self, RawExpr::App(_) | RawExpr::Lambda(_))
+    }
+
+    #[inline]
+    fn has_nested_children(&self) -> bool {
+        match self {
+            RawExpr::App(app) => app.fun.is_nested() || app.arg.is_nested(),
+            RawExpr::Lambda(lambda) => lambda.body.is_nested(),
+            _ => false,
+        }
+    }
+
+    fn drop_children(&mut self) {
+        let mut stack = Vec::new();
+        let mut next = self.take_nested_children(&mut stack);
+        while let Some(mut expr) = next.or_else(|| stack.pop()) {
+            next = expr.take_nested_children(&mut stack);
+        }
+    }
+
+    /* Replaces the nested children with leaves, returning one of them and pushing the other (if
+     * any) onto the stack. */
+    fn take_nested_children(&mut self, stack: &mut Vec<RawExpr>) -> Option<RawExpr> {
+        match self {
+            RawExpr::App(app) => {
+                let fun = app.fun.is_nested().then(|| take(&mut app.fun));
+                let arg = app.arg.is_nested().then(|| take(&mut app.arg));
+                match (fun, arg) {
+                    (Some(fun), Some(arg)) => {
+                        stack.push(arg);
+                        Some(fun)
+                    }
+                    (fun, arg) => fun.or(arg),
+                }
+            }
+            RawExpr::Lambda(lambda) => lambda.body.is_nested().then(|| take(&mut lambda.body)),
+            _ => None,
         }
     }
 }
@@ -140,6 +454,35 @@ impl RawAppExpr {
         None
     }
 
+    /* If the head of this application is a primitive that takes exactly the given arguments, call
+     * it. Should only be called when all arguments are reduced. */
+    fn try_get_primitive_result(&self) -> Option<RawExpr> {
+        let prim = self.get_called_primitive()?;
+        let mut args = Vec::with_capacity(prim.arity as usize);
+        args.push(&self.arg);
+        let mut fun = &self.fun;
+        while let RawExpr::App(app) = fun {
+            args.push(&app.arg);
+            fun = &app.fun;
+        }
+        args.reverse();
+        prim.call(&args)
+    }
+
+    // Returns the head of this application if it is a primitive that takes exactly its arguments.
+    fn get_called_primitive(&self) -> Option<&Primitive> {
+        let mut fun = &self.fun;
+        let mut arg_count = 1;
+        while let RawExpr::App(app) = fun {
+            fun = &app.fun;
+            arg_count += 1;
+        }
+        match fun {
+            RawExpr::Prim(prim) if prim.arity == arg_count => Some(prim),
+            _ => None,
+        }
+    }
+
     fn substitute(&mut self, idx: DeBruijnIndex, value: &mut RawExpr, may_take_value: bool) {
         // Optimization: If arg does not reference the variable, allow fun to take the value.
         if may_take_value && self.arg.try_unshift(idx, 1) {
@@ -188,6 +531,14 @@ pub struct RawLambdaExpr {
 }
 
 impl RawLambdaExpr {
+    fn is_eta_reducible(&self) -> bool {
+        if let RawExpr::App(app) = &self.body {
+            matches!(app.arg, RawExpr::Var(0)) && !app.fun.references_var(0)
+        } else {
+            false
+        }
+    }
+
     // Note: Invalidates self if and only if eta reduction is possible.
     fn try_get_eta_reduced(&mut self) -> Option<RawExpr> {
         if let RawExpr::App(app) = &mut self.body {
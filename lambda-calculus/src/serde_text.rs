@@ -0,0 +1,16 @@
+/* Serializes expressions as strings in the text syntax, for use with
+ * `#[serde(with = "lambda_calculus::serde_text")]`. The string is produced by Display, so binders
+ * may be renamed, and the expression must be closed. */
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+use crate::raw_expr::*;
+
+pub fn serialize<S: Serializer>(expr: &RawExpr, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(expr)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RawExpr, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
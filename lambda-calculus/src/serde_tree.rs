@@ -0,0 +1,132 @@
+/* Structural (de)serialization of expressions as the sequence of their nodes in prefix order (see
+ * prefix.rs), e.g. `[{"Lambda":"x"},"App",{"Var":0},{"Var":0}]` for `λx.x x` in JSON. Unlike a
+ * nested representation, this does not run into the recursion limits of deserializers for large
+ * expressions. Deserialization checks that each De Bruijn index refers to an enclosing lambda, so
+ * the result is always closed. */
+
+use std::{fmt, rc::Rc};
+
+use serde::{
+    de,
+    ser::{self, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{prefix::*, raw_expr::*};
+
+#[derive(Serialize)]
+#[serde(rename = "Node")]
+enum NodeRef<'a> {
+    Var(DeBruijnIndex),
+    App,
+    Lambda(&'a str),
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Node")]
+enum NodeData {
+    Var(u64),
+    App,
+    Lambda(String),
+}
+
+impl<'a> NodeRef<'a> {
+    fn new(expr: &'a RawExpr) -> Result<Self, String> {
+        match expr {
+            RawExpr::Var(idx) => Ok(NodeRef::Var(*idx)),
+            RawExpr::App(_) => Ok(NodeRef::App),
+            RawExpr::Lambda(lambda) => Ok(NodeRef::Lambda(&lambda.param.name)),
+            RawExpr::Prim(prim) => Err(format!("Primitive {} cannot be serialized.", prim.name)),
+        }
+    }
+}
+
+// Serializes the given node (if any), followed by the nodes of the given subexpressions.
+fn serialize_nodes<'a, S: Serializer>(
+    serializer: S,
+    node: Option<NodeRef<'a>>,
+    exprs: &[&'a RawExpr],
+) -> Result<S::Ok, S::Error> {
+    let len = node.iter().count()
+        + exprs
+            .iter()
+            .map(|expr| expr.prefix_iter().count())
+            .sum::<usize>();
+    let mut seq = serializer.serialize_seq(Some(len))?;
+    if let Some(node) = node {
+        seq.serialize_element(&node)?;
+    }
+    for expr in exprs.iter().flat_map(|expr| expr.prefix_iter()) {
+        seq.serialize_element(&NodeRef::new(expr).map_err(ser::Error::custom)?)?;
+    }
+    seq.end()
+}
+
+impl Serialize for RawExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_nodes(serializer, None, &[self])
+    }
+}
+
+impl Serialize for RawAppExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_nodes(serializer, Some(NodeRef::App), &[&self.fun, &self.arg])
+    }
+}
+
+impl Serialize for RawLambdaExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let node = NodeRef::Lambda(&self.param.name);
+        serialize_nodes(serializer, Some(node), &[&self.body])
+    }
+}
+
+struct NodesVisitor;
+
+impl<'de> de::Visitor<'de> for NodesVisitor {
+    type Value = RawExpr;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of expression nodes")
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<RawExpr, A::Error> {
+        let mut builder = PrefixBuilder::default();
+        while let Some(node) = seq.next_element()? {
+            let node = match node {
+                NodeData::Var(idx) => PrefixNode::Var(idx),
+                NodeData::App => PrefixNode::App,
+                NodeData::Lambda(name) => PrefixNode::Lambda(Rc::new(Param { name })),
+            };
+            if let Some(expr) = builder.push(node).map_err(de::Error::custom)? {
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::custom("Unexpected data after expression."));
+                }
+                return Ok(expr);
+            }
+        }
+        Err(de::Error::custom("Unexpected end of expression."))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(NodesVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for RawAppExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RawExpr::deserialize(deserializer)?
+            .into_app()
+            .map_err(|_| de::Error::custom("Expected an application."))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawLambdaExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RawExpr::deserialize(deserializer)?
+            .into_lambda()
+            .map_err(|_| de::Error::custom("Expected a lambda."))
+    }
+}
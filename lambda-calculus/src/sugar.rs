@@ -0,0 +1,57 @@
+use std::rc::Rc;
+
+use crate::raw_expr::*;
+
+impl RawExpr {
+    /* `let x = value in body`, which is just `(λx.body) value`. The body lives in the context of
+     * value, extended by param. */
+    pub fn new_let(param: Rc<Param>, value: RawExpr, body: RawExpr) -> RawExpr {
+        RawAppExpr {
+            fun: RawLambdaExpr { param, body }.into(),
+            arg: value,
+        }
+        .into()
+    }
+
+    /* `letrec f = value in body`, which becomes `(λf.body) (Y (λf.value))`. In contrast to `let`,
+     * value may also reference param. */
+    pub fn new_letrec(param: Rc<Param>, value: RawExpr, body: RawExpr) -> RawExpr {
+        let fixed_point = RawAppExpr {
+            fun: RawExpr::y_combinator(),
+            arg: RawLambdaExpr {
+                param: param.clone(),
+                body: value,
+            }
+            .into(),
+        };
+        RawExpr::new_let(param, fixed_point.into(), body)
+    }
+
+    // λf.(λx.f (x x)) (λx.f (x x))
+    pub fn y_combinator() -> RawExpr {
+        let f = Rc::new(Param { name: "f".into() });
+        let x = Rc::new(Param { name: "x".into() });
+        let half: RawExpr = RawLambdaExpr {
+            param: x,
+            body: RawAppExpr {
+                fun: RawExpr::Var(1),
+                arg: RawAppExpr {
+                    fun: RawExpr::Var(0),
+                    arg: RawExpr::Var(0),
+                }
+                .into(),
+            }
+            .into(),
+        }
+        .into();
+        RawLambdaExpr {
+            param: f,
+            body: RawAppExpr {
+                fun: half.clone(),
+                arg: half,
+            }
+            .into(),
+        }
+        .into()
+    }
+}
@@ -0,0 +1,66 @@
+use lambda_calculus::raw_expr::*;
+
+// Version 1 with a single parameter name `x`, followed by the given expression.
+fn with_header(body: &[u8]) -> Vec<u8> {
+    [&[1, 1, 1, b'x'], body].concat()
+}
+
+#[test]
+fn round_trip() {
+    let expr: RawExpr = "λf x. f (λy. f y x) (λx. x)".parse().unwrap();
+    let bytes = expr.to_binary().unwrap();
+    let decoded = RawExpr::from_binary(&bytes).unwrap();
+    assert_eq!(decoded.to_string(), expr.to_string());
+}
+
+#[test]
+fn deeply_nested_round_trip() {
+    let depth = 100000;
+    let source = format!("λx.{}x{}", "(x ".repeat(depth), ")".repeat(depth));
+    let expr: RawExpr = source.parse().unwrap();
+    let bytes = expr.to_binary().unwrap();
+    let decoded = RawExpr::from_binary(&bytes).unwrap();
+    assert_eq!(decoded.to_binary().unwrap(), bytes);
+}
+
+#[test]
+fn deeply_nested_lambdas() {
+    let lambdas = [2, 0].repeat(1000000);
+    let expr = RawExpr::from_binary(&with_header(&[&lambdas[..], &[0, 0]].concat())).unwrap();
+    assert_eq!(expr.to_binary().unwrap().len(), 4 + lambdas.len() + 2);
+    let err = RawExpr::from_binary(&with_header(&lambdas)).err().unwrap();
+    assert_eq!(err, "Unexpected end of data.");
+}
+
+#[test]
+fn rejects_invalid_data() {
+    let reject = |bytes: &[u8], expected: &str| {
+        let err = RawExpr::from_binary(bytes).err().unwrap();
+        assert_eq!(err, expected);
+    };
+    reject(
+        &with_header(&[2, 0, 0, 1]),
+        "De Bruijn index 1 is out of range.",
+    );
+    reject(&with_header(&[0, 0]), "De Bruijn index 0 is out of range.");
+    reject(
+        &with_header(&[2, 1, 0, 0]),
+        "Parameter name 1 is out of range.",
+    );
+    reject(
+        &with_header(&[2, 0, 0, 0, 0]),
+        "Unexpected data after expression.",
+    );
+    reject(&with_header(&[3]), "Invalid tag: 3");
+    reject(&[2, 0, 2, 0, 0, 0], "Unsupported format version: 2");
+    reject(&[], "Unexpected end of data.");
+}
+
+#[test]
+fn rejects_primitives() {
+    let prim = RawExpr::primitive("p", 0, |_| None);
+    assert_eq!(
+        prim.to_binary().err().unwrap(),
+        "Primitive p cannot be encoded."
+    );
+}
@@ -0,0 +1,52 @@
+use lambda_calculus::raw_expr::*;
+
+#[test]
+fn round_trip() {
+    let expr: RawExpr = "λx y. x".parse().unwrap();
+    assert_eq!(expr.to_blc().unwrap(), "0000110");
+    let expr: RawExpr = "λf x. f (f x)".parse().unwrap();
+    let bits = expr.to_blc().unwrap();
+    assert_eq!(bits, "0000011100111010");
+    let decoded = RawExpr::from_blc(&bits).unwrap();
+    assert_eq!(decoded.to_church_numeral(), Ok(2));
+    assert!(RawExpr::from_blc(" 0000 110\n").is_ok());
+}
+
+#[test]
+fn bytes_with_padding() {
+    let expr: RawExpr = "λx y. x".parse().unwrap();
+    let bytes = expr.to_blc_bytes().unwrap();
+    assert_eq!(bytes, [0b0000_1100]);
+    assert_eq!(
+        RawExpr::from_blc_bytes(&bytes).unwrap().to_blc().unwrap(),
+        "0000110"
+    );
+
+    // Only zero bits within the last byte may follow the expression.
+    let err = RawExpr::from_blc_bytes(&[0b0000_1101]).err().unwrap();
+    assert_eq!(err, "Unexpected bits after expression.");
+    let err = RawExpr::from_blc_bytes(&[0b0000_1100, 0]).err().unwrap();
+    assert_eq!(err, "Unexpected bits after expression.");
+    let err = RawExpr::from_blc_bytes(&[0b0000_0000]).err().unwrap();
+    assert_eq!(err, "Unexpected end of input.");
+}
+
+#[test]
+fn deeply_nested_lambdas() {
+    let lambdas = "00".repeat(1000000);
+    let expr = RawExpr::from_blc(&format!("{lambdas}10")).unwrap();
+    assert_eq!(expr.to_blc().unwrap().len(), lambdas.len() + 2);
+    let err = RawExpr::from_blc(&lambdas).err().unwrap();
+    assert_eq!(err, "Unexpected end of input.");
+}
+
+#[test]
+fn rejects_invalid_input() {
+    let reject = |s: &str, expected: &str| {
+        assert_eq!(RawExpr::from_blc(s).err().unwrap(), expected);
+    };
+    reject("10", "De Bruijn index 0 is out of range.");
+    reject("00110", "De Bruijn index 1 is out of range.");
+    reject("001010", "Unexpected bits after expression.");
+    reject("0012", "Expected 0 or 1 instead of: 2");
+}
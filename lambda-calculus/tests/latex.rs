@@ -0,0 +1,25 @@
+use lambda_calculus::raw_expr::*;
+
+fn latex(source: &str) -> String {
+    source.parse::<RawExpr>().unwrap().to_latex()
+}
+
+#[test]
+fn names() {
+    assert_eq!(latex("λx. x"), r"\lambda x.\, x");
+    assert_eq!(
+        latex("λfoo_bar. foo_bar"),
+        r"\lambda \mathit{foo\_bar}.\, \mathit{foo\_bar}"
+    );
+    assert_eq!(latex("λx₁'. x₁'"), r"\lambda x'_{1}.\, x'_{1}");
+}
+
+#[test]
+fn unicode_names() {
+    assert_eq!(latex("λα. α"), r"\lambda \alpha.\, \alpha");
+    assert_eq!(
+        latex("λαx Ω₂. Ω₂"),
+        r"\lambda \mathit{\alpha x}.\, \lambda \Omega_{2}.\, \Omega_{2}"
+    );
+    assert_eq!(latex("λä. ä"), r"\lambda \text{ä}.\, \text{ä}");
+}
@@ -0,0 +1,40 @@
+use lambda_calculus::raw_expr::*;
+
+const DEPTH: usize = 100000;
+
+// Parsing and dropping must not overflow the stack, regardless of the nesting depth.
+fn parse_and_drop(source: &str) {
+    let expr: RawExpr = source.parse().unwrap();
+    drop(expr);
+}
+
+#[test]
+fn nested_parens_and_binders() {
+    parse_and_drop(&format!("{}x{}", "(λx.".repeat(DEPTH), ")".repeat(DEPTH)));
+}
+
+#[test]
+fn chained_binders() {
+    parse_and_drop(&format!("λ{}. x", " x".repeat(DEPTH)));
+}
+
+#[test]
+fn nested_applications() {
+    parse_and_drop(&format!("λx.{}x{}", "(x ".repeat(DEPTH), ")".repeat(DEPTH)));
+}
+
+#[test]
+fn left_nested_applications() {
+    parse_and_drop(&format!("λx.{}", "x ".repeat(DEPTH)));
+}
+
+#[test]
+fn move_out_of_expression() {
+    let expr: RawExpr = "λx. x (λy. y)".parse().unwrap();
+    let lambda = expr.into_lambda().ok().unwrap();
+    assert_eq!(lambda.param.name, "x");
+    let app = lambda.body.into_app().ok().unwrap();
+    assert!(matches!(app.fun, RawExpr::Var(0)));
+    let arg = app.arg.into_app().err().unwrap();
+    assert_eq!(arg.into_lambda().ok().unwrap().param.name, "y");
+}
@@ -0,0 +1,155 @@
+use lambda_calculus::{display::*, encoding::*, env::*, error::*, parse::*, raw_expr::*};
+
+fn parse_error(source: &str) -> ParseError {
+    match source.parse::<RawExpr>() {
+        Ok(expr) => panic!("{source} parsed as {expr}"),
+        Err(err) => err,
+    }
+}
+
+#[test]
+fn numeral_limit() {
+    let max = MAX_NUMERAL.to_string();
+    let expr: RawExpr = max.parse().unwrap();
+    assert_eq!(expr.to_church_numeral(), Ok(MAX_NUMERAL));
+    let expr: RawExpr = format!("{max}s").parse().unwrap();
+    assert_eq!(expr.to_scott_numeral(), Ok(MAX_NUMERAL));
+
+    for source in [(MAX_NUMERAL + 1).to_string(), "99999999999".into()] {
+        let err = parse_error(&format!("λx. x {source}"));
+        assert_eq!(err.kind, ParseErrorKind::NumeralTooLarge(source));
+        assert_eq!(err.column, 7);
+    }
+}
+
+fn parse_de_bruijn(source: &str, env: &Environment) -> Result<RawExpr, ParseError> {
+    let mut input = ParserInput::with_env(source, env);
+    input.options.de_bruijn = true;
+    RawExpr::parse_all(input)
+}
+
+#[test]
+fn de_bruijn_names_refer_to_environment() {
+    let env = Environment::prelude();
+    let options = DisplayOptions {
+        de_bruijn: true,
+        ..Default::default()
+    };
+    let expr = parse_de_bruijn("λ λ not 1", &env).ok().unwrap();
+    let expected = RawExpr::parse_with_env("λa b. not a", &env).unwrap();
+    assert_eq!(
+        expr.display_with(options).to_string(),
+        expected.display_with(options).to_string()
+    );
+
+    let err = parse_de_bruijn("λ λ x", &env).err().unwrap();
+    assert_eq!(err.kind, ParseErrorKind::UnknownVariable("x".into()));
+    assert_eq!(err.column, 5);
+}
+
+#[test]
+fn error_positions() {
+    let source = "λx.\n  (x y)";
+    let err = parse_error(source);
+    assert_eq!(err.kind, ParseErrorKind::UnknownVariable("y".into()));
+    assert_eq!((err.offset, err.line, err.column), (10, 2, 6));
+    assert_eq!(
+        err.render(source),
+        "Variable y not found. (line 2, column 6)\n  (x y)\n     ^"
+    );
+
+    // Columns are counted in characters, and tabs are kept in the rendered caret line.
+    let source = "λα.\tα β";
+    let err = parse_error(source);
+    assert_eq!((err.offset, err.line, err.column), (9, 1, 7));
+    assert_eq!(
+        err.render(source),
+        "Variable β not found. (line 1, column 7)\nλα.\tα β\n   \t  ^"
+    );
+}
+
+#[test]
+fn unexpected_tokens() {
+    let err = parse_error("(λx. x");
+    assert_eq!(err.kind, ParseErrorKind::UnexpectedEnd);
+    assert!(err.expected.contains(&")".into()), "{:?}", err.expected);
+    assert_eq!((err.offset, err.column), (7, 7));
+    assert!(err.to_string().starts_with("Expected "), "{err}");
+    assert!(
+        err.to_string()
+            .ends_with(" at end of input. (line 1, column 7)"),
+        "{err}"
+    );
+
+    let err = parse_error("λx. x)");
+    assert_eq!(err.kind, ParseErrorKind::UnexpectedToken(")".into()));
+    assert_eq!(err.expected, ["expression", "end of input"]);
+    assert_eq!(
+        err.to_string(),
+        "Expected expression or end of input instead of: ) (line 1, column 6)"
+    );
+}
+
+fn kinds_and_columns(errors: &[ParseError]) -> Vec<(ParseErrorKind, usize)> {
+    errors
+        .iter()
+        .map(|err| (err.kind.clone(), err.column))
+        .collect()
+}
+
+#[test]
+fn recovering_parse() {
+    let source = "λx. x y (z";
+    let (expr, errors) = RawExpr::parse_all_recovering(ParserInput::new(source));
+    assert_eq!(
+        kinds_and_columns(&errors),
+        [
+            (ParseErrorKind::UnknownVariable("y".into()), 7),
+            (ParseErrorKind::UnknownVariable("z".into()), 10),
+            (ParseErrorKind::UnexpectedEnd, 11),
+        ]
+    );
+    // Unknown variables are replaced with opaque placeholders of the same name.
+    assert_eq!(expr.to_string(), "λx.(x y z)");
+    assert!(expr.references_primitive("y") && expr.references_primitive("z"));
+    assert_eq!(parse_error(source), errors[0]);
+
+    let (expr, errors) = RawExpr::parse_all_recovering(ParserInput::new("λx. x) y"));
+    assert_eq!(
+        kinds_and_columns(&errors),
+        [
+            (ParseErrorKind::UnexpectedToken(")".into()), 6),
+            (ParseErrorKind::UnknownVariable("y".into()), 8),
+        ]
+    );
+    assert_eq!(expr.to_string(), "(λx.x) y");
+
+    let (expr, errors) = RawExpr::parse_all_recovering(ParserInput::new("λx y. y x"));
+    assert!(errors.is_empty());
+    assert_eq!(expr.to_string(), "λx.λy.(y x)");
+}
+
+#[test]
+fn recovering_definitions() {
+    let source = "a = λx. x\nb = a y\nc = (a\nd = λx. a x\n";
+    let mut env = Environment::new();
+    let errors = env.define_all_recovering(source);
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|err| (err.kind.clone(), err.line))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            (ParseErrorKind::UnknownVariable("y".into()), 2),
+            (ParseErrorKind::UnexpectedToken("d".into()), 4),
+        ]
+    );
+    // Definitions with errors are added in a partial form, and later ones are still parsed.
+    assert_eq!(env.get("b").unwrap().to_string(), "(λx.x) y");
+    assert_eq!(env.get("c").unwrap().to_string(), "λx.x");
+    assert_eq!(env.get("d").unwrap().to_string(), "λx.((λx1.x1) x)");
+
+    let err = Environment::new().define_all(source).err().unwrap();
+    assert_eq!(err, errors[0]);
+}
@@ -0,0 +1,97 @@
+use std::{env, fs, path::PathBuf, process};
+
+use lambda_calculus::{env::*, program::*};
+
+// Writes the given files to a fresh temporary directory, and returns the path of the first one.
+fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("lambda-calculus-{test}-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (name, source) in files {
+        fs::write(dir.join(name), source).unwrap();
+    }
+    dir.join(files[0].0)
+}
+
+fn load(test: &str, files: &[(&str, &str)]) -> Result<Program, String> {
+    let path = write_files(test, files);
+    let result = Program::load(&path, &Environment::prelude());
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    result
+}
+
+fn run(test: &str, files: &[(&str, &str)]) -> u64 {
+    let mut main = load(test, files).unwrap().main.unwrap();
+    main.reduce(&mut 10000);
+    main.to_church_numeral().unwrap()
+}
+
+const LISTS: (&str, &str) = ("lists.lam", "ident = λx. x\nkonst = λx y. x\n");
+
+#[test]
+fn import_all() {
+    let main = (
+        "main.lam",
+        "import \"lists.lam\"\nmain = konst (ident 1) 2\n",
+    );
+    assert_eq!(run("import_all", &[main, LISTS]), 1);
+}
+
+#[test]
+fn import_namespace() {
+    let main = ("main.lam", "import lists\nmain = lists.konst 1 2\n");
+    assert_eq!(run("import_namespace", &[main, LISTS]), 1);
+
+    let main = ("main.lam", "import lists\nmain = konst 1 2\n");
+    let err = load("import_namespace_unqualified", &[main, LISTS])
+        .err()
+        .unwrap();
+    assert!(err.contains("Variable konst not found."), "{err}");
+}
+
+#[test]
+fn import_names() {
+    let main = (
+        "main.lam",
+        "import lists (konst)\nmain = konst (lists.ident 1) 2\n",
+    );
+    assert_eq!(run("import_names", &[main, LISTS]), 1);
+
+    let main = ("main.lam", "import lists (konst)\nmain = ident 1\n");
+    let err = load("import_names_other", &[main, LISTS]).err().unwrap();
+    assert!(err.contains("Variable ident not found."), "{err}");
+
+    let main = ("main.lam", "import lists (nope)\nmain = 1\n");
+    let err = load("import_names_unknown", &[main, LISTS]).err().unwrap();
+    assert!(err.contains("Variable nope not found."), "{err}");
+}
+
+#[test]
+fn shared_import() {
+    let files = [
+        ("main.lam", "import a\nimport b\nmain = a.one (b.two 3)\n"),
+        ("a.lam", "import \"lists.lam\"\none = konst 1\n"),
+        ("b.lam", "import \"lists.lam\"\ntwo = λx. ident 2\n"),
+        LISTS,
+    ];
+    assert_eq!(run("shared_import", &files), 1);
+}
+
+#[test]
+fn import_cycle() {
+    let files = [
+        ("main.lam", "import a\nmain = a.x\n"),
+        ("a.lam", "import b\nx = b.y\n"),
+        ("b.lam", "import a\ny = λx. x\n"),
+    ];
+    let err = load("import_cycle", &files).err().unwrap();
+    assert!(err.contains("Import cycle: "), "{err}");
+    assert!(err.contains("a.lam -> "), "{err}");
+}
+
+#[test]
+fn missing_import() {
+    let main = ("main.lam", "import missing\nmain = 1\n");
+    let err = load("missing_import", &[main]).err().unwrap();
+    assert!(err.contains("Cannot import missing.lam"), "{err}");
+}
@@ -0,0 +1,76 @@
+use std::{cell::Cell, rc::Rc};
+
+use lambda_calculus::{env::*, raw_expr::*};
+
+fn reduce_to_numeral(source: &str) -> u64 {
+    let mut expr = RawExpr::parse_with_env(source, &Environment::prelude()).unwrap();
+    let mut limit = 100000;
+    expr.reduce(&mut limit);
+    assert!(limit > 0, "reduction limit reached");
+    expr.to_church_numeral().unwrap()
+}
+
+#[test]
+fn letrec_terminates() {
+    let source = "letrec f = λn. if (iszero n) 0 (f (pred n)) in f 3";
+    assert_eq!(reduce_to_numeral(source), 0);
+    let source = "letrec fact = λn. if (iszero n) 1 (mul n (fact (pred n))) in fact 4";
+    assert_eq!(reduce_to_numeral(source), 24);
+}
+
+#[test]
+fn prelude_fixed_points_terminate() {
+    for combinator in ["Y", "Z"] {
+        let source = format!("{combinator} (λf n. if (iszero n) 0 (f (pred n))) 3");
+        assert_eq!(reduce_to_numeral(&source), 0);
+    }
+}
+
+#[test]
+fn next_redex_skips_primitives_without_result() {
+    let mut env = Environment::new();
+    env.register_primitive("stuck", 1, |_| None);
+    let mut expr = RawExpr::parse_with_env("λy. stuck y ((λx. x) y)", &env).unwrap();
+    let (_, kind) = expr.next_redex().unwrap();
+    assert_eq!(kind, ReductionKind::Beta);
+    let mut limit = 1;
+    assert!(expr.reduce(&mut limit));
+    assert_eq!(expr.to_string(), "λy.(stuck y y)");
+    assert!(expr.next_redex().is_none());
+}
+
+#[test]
+fn reduce_step_returns_contracted_redex() {
+    let mut expr: RawExpr = "λz. (λx. x) ((λy. y) z)".parse().unwrap();
+    let (path, kind) = expr.reduce_step().unwrap();
+    assert_eq!((path, kind), (vec![Child::Body], ReductionKind::Beta));
+    assert_eq!(expr.to_string(), "λz.((λy.y) z)");
+    let (path, kind) = expr.reduce_step().unwrap();
+    assert_eq!((path, kind), (vec![], ReductionKind::Eta));
+    assert_eq!(expr.to_string(), "λy.y");
+    assert!(expr.reduce_step().is_none());
+}
+
+#[test]
+fn reduce_to_latex_calls_primitives_once_per_step() {
+    let calls = Rc::new(Cell::new(0));
+    let mut env = Environment::new();
+    let counter = calls.clone();
+    env.register_primitive("io", 2, move |args| {
+        counter.set(counter.get() + 1);
+        Some(args[0].clone())
+    });
+    let mut expr = RawExpr::parse_with_env("λy. io ((λx. x) y) y", &env).unwrap();
+    let latex = expr.reduce_to_latex(&mut 10, true);
+    assert_eq!(calls.get(), 1);
+    assert!(
+        latex.contains(r"\underline{(\textsf{io}\; y\; y)}"),
+        "{latex}"
+    );
+}
+
+#[test]
+fn latex_escapes_primitive_names() {
+    let prim = RawExpr::primitive("50%_&#${x}", 0, |_| None);
+    assert_eq!(prim.to_latex(), r"\textsf{50\%\_\&\#\$\{x\}}");
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "serde")]
+
+use lambda_calculus::raw_expr::*;
+
+fn round_trip_json(expr: &RawExpr) -> RawExpr {
+    let json = serde_json::to_string(expr).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let expr: RawExpr = "λf x. f (f x)".parse().unwrap();
+    let json = serde_json::to_string(&expr).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"Lambda":"f"},{"Lambda":"x"},"App",{"Var":1},"App",{"Var":1},{"Var":0}]"#
+    );
+    assert_eq!(round_trip_json(&expr).to_string(), expr.to_string());
+}
+
+#[test]
+fn large_terms() {
+    let expr = RawExpr::church_numeral(1000);
+    assert_eq!(round_trip_json(&expr).to_church_numeral(), Ok(1000));
+    let expr = RawExpr::church_string("Hello, world!");
+    assert_eq!(
+        round_trip_json(&expr).to_church_string().unwrap(),
+        "Hello, world!"
+    );
+
+    let depth = 100000;
+    let source = format!("λx.{}x{}", "(x ".repeat(depth), ")".repeat(depth));
+    let expr: RawExpr = source.parse().unwrap();
+    let result = round_trip_json(&expr);
+    assert_eq!(result.to_binary().unwrap(), expr.to_binary().unwrap());
+}
+
+#[test]
+fn parts_of_expressions() {
+    let expr: RawExpr = "λx. x (λy. y)".parse().unwrap();
+    let lambda = expr.into_lambda().ok().unwrap();
+    let json = serde_json::to_string(&lambda).unwrap();
+    let result: RawLambdaExpr = serde_json::from_str(&json).unwrap();
+    assert_eq!(RawExpr::from(result).to_string(), "λx.(x (λy.y))");
+    assert!(serde_json::from_str::<RawAppExpr>(&json).is_err());
+}
+
+#[test]
+fn invalid_data() {
+    let error = |json: &str| {
+        serde_json::from_str::<RawExpr>(json)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert!(error(r#"[{"Lambda":"x"},{"Var":5}]"#).contains("De Bruijn index 5 is out of range."));
+    assert!(error(r#"[{"Var":0}]"#).contains("De Bruijn index 0 is out of range."));
+    assert!(error(r#"[{"Lambda":"x"},"App",{"Var":0}]"#).contains("Unexpected end of expression."));
+    assert!(error(r#"[{"Lambda":"x"},{"Var":0},"App"]"#).contains("Unexpected data after"));
+}
+
+#[test]
+fn primitives_cannot_be_serialized() {
+    let prim = RawExpr::primitive("p", 0, |_| None);
+    let err = serde_json::to_string(&prim).err().unwrap();
+    assert!(err
+        .to_string()
+        .contains("Primitive p cannot be serialized."));
+}
@@ -1,12 +1,38 @@
-use std::io::stdin;
+use std::{io::stdin, process::exit};
 
-use lambda_calculus::raw_expr::*;
+use lambda_calculus::{display::*, dot::*, encoding::*, env::*, parse::*, program::*, raw_expr::*};
 use lambda_calculus_macro::raw_expr;
 
+#[derive(Default)]
+struct Settings {
+    display: DisplayOptions,
+    parser: ParserOptions,
+}
+
 fn main() {
+    if let Some(path) = std::env::args().nth(1) {
+        run_file(&path);
+        return;
+    }
     println!(
         "Enter a lambda expression to reduce it, or an empty line to print examples and quit."
     );
+    println!("Expressions may span multiple lines as long as parentheses are not balanced yet.");
+    println!("Use `name = expr` to define a name, `:defs` to list definitions, and `:undef name` to remove one.");
+    println!(
+        "Use `:set encodings on` to print Church numerals, booleans, pairs, and lists as such."
+    );
+    println!("Use `:set collapse on` to print `λx y.e` instead of `λx.λy.e`, and `:set spaces on` to print `λx. e`.");
+    println!("Use `:set syntax ascii` or `:set syntax haskell` to print `\\x.e` or `\\x -> e`.");
+    println!("Use `:set debruijn on` to print and parse De Bruijn indices, as in `λ λ 1 0`.");
+    println!(
+        "Use `:set numerals scott` to parse numerals as Scott numerals instead of Church numerals."
+    );
+    println!("Use `:latex expr` to print the reduction of expr as LaTeX.");
+    println!("Use `:blc bits` to reduce an expression given in Binary Lambda Calculus.");
+    println!("Use `:dot expr` to print the syntax tree of expr in Graphviz DOT format.");
+    let mut env = Environment::prelude();
+    let mut settings = Settings::default();
     let mut buffer = String::new();
     loop {
         let read = stdin().read_line(&mut buffer).unwrap();
@@ -18,25 +44,162 @@ fn main() {
             print_examples();
             break;
         }
-        match input.parse::<RawExpr>() {
-            Ok(mut expr) => {
-                println!("input: {expr}");
-                let mut limit = 10000;
-                if expr.reduce(&mut limit) {
-                    println!("reduced: {expr}");
-                    if limit == 0 {
-                        println!("(reduction limit reached)");
-                    }
-                } else {
-                    println!("not reducible");
+        if !input.starts_with(':') && is_incomplete(input) {
+            // Keep reading lines until all parentheses and comments are closed.
+            continue;
+        }
+        if let Some(command) = input.strip_prefix(':') {
+            run_command(command, &mut env, &mut settings);
+        } else {
+            let parser_input = || ParserInput {
+                options: settings.parser,
+                ..ParserInput::with_env(input, &env)
+            };
+            match RawExpr::parse_definition(parser_input()) {
+                Ok(Some((name, expr))) => {
+                    println!("{name} = {}", expr.display_with(settings.display));
+                    env.define(name, expr);
                 }
+                Ok(None) => match RawExpr::parse_all(parser_input()) {
+                    Ok(expr) => reduce_and_print(expr, settings.display),
+                    Err(err) => eprintln!("{}", err.render(input)),
+                },
+                Err(err) => eprintln!("{}", err.render(input)),
             }
-            Err(msg) => eprintln!("{msg}"),
         }
         buffer.clear();
     }
 }
 
+// Loads a program file and reduces its main expression, or lists its definitions if it has none.
+fn run_file(path: &str) {
+    match Program::load(path, &Environment::prelude()) {
+        Ok(program) => match program.main {
+            Some(main) => reduce_and_print(main, DisplayOptions::default()),
+            None => {
+                for (name, expr) in &program.definitions {
+                    println!("{name} = {expr}");
+                }
+            }
+        },
+        Err(msg) => {
+            eprintln!("{msg}");
+            exit(1);
+        }
+    }
+}
+
+fn reduce_and_print(mut expr: RawExpr, options: DisplayOptions) {
+    println!("input: {}", expr.display_with(options));
+    let mut limit = 10000;
+    if expr.reduce(&mut limit) {
+        println!("reduced: {}", expr.display_with(options));
+        if limit == 0 {
+            println!("(reduction limit reached)");
+        }
+    } else {
+        println!("not reducible");
+    }
+}
+
+fn run_command(command: &str, env: &mut Environment, settings: &mut Settings) {
+    if let Some(input) = command.strip_prefix("latex ") {
+        if let Some(mut expr) = parse_expr(input, env, settings) {
+            let mut limit = 100;
+            print!("{}", expr.reduce_to_latex(&mut limit, true));
+        }
+        return;
+    }
+    if let Some(input) = command.strip_prefix("blc ") {
+        match RawExpr::from_blc(input) {
+            Ok(expr) => reduce_and_print(expr, settings.display),
+            Err(msg) => eprintln!("{msg}"),
+        }
+        return;
+    }
+    if let Some(input) = command.strip_prefix("dot ") {
+        if let Some(expr) = parse_expr(input, env, settings) {
+            print!("{}", expr.to_dot(DotOptions { back_edges: true }));
+        }
+        return;
+    }
+    let mut words = command.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("defs"), None, _) => {
+            for (name, expr) in env.iter() {
+                println!("{name} = {}", expr.display_with(settings.display));
+            }
+        }
+        (Some("set"), Some(option), Some(value)) if words.next().is_none() => {
+            if let Err(msg) = set_option(settings, option, value) {
+                eprintln!("{msg}");
+            }
+        }
+        (Some("undef"), Some(name), None) => {
+            if env.undefine(name).is_none() {
+                eprintln!("{name} is not defined.");
+            }
+        }
+        _ => eprintln!("Unknown command: {command}"),
+    }
+}
+
+fn parse_expr(input: &str, env: &Environment, settings: &Settings) -> Option<RawExpr> {
+    let parser_input = ParserInput {
+        options: settings.parser,
+        ..ParserInput::with_env(input, env)
+    };
+    match RawExpr::parse_all(parser_input) {
+        Ok(expr) => Some(expr),
+        Err(err) => {
+            eprintln!("{}", err.render(input));
+            None
+        }
+    }
+}
+
+fn set_option(settings: &mut Settings, option: &str, value: &str) -> Result<(), String> {
+    match option {
+        "encodings" => settings.display.recognize_encodings = parse_on_off(value)?,
+        "collapse" => settings.display.collapse_binders = parse_on_off(value)?,
+        "spaces" => settings.display.space_after_dot = parse_on_off(value)?,
+        "syntax" => {
+            settings.display.syntax = match value {
+                "unicode" => LambdaSyntax::Unicode,
+                "ascii" => LambdaSyntax::Ascii,
+                "haskell" => LambdaSyntax::Haskell,
+                _ => {
+                    return Err(format!(
+                        "Expected unicode, ascii, or haskell instead of: {value}"
+                    ))
+                }
+            }
+        }
+        "debruijn" => {
+            let value = parse_on_off(value)?;
+            settings.display.de_bruijn = value;
+            settings.parser.de_bruijn = value;
+        }
+        "numerals" => {
+            settings.parser.numerals = match value {
+                "church" => NumeralEncoding::Church,
+                "scott" => NumeralEncoding::Scott,
+                _ => return Err(format!("Expected church or scott instead of: {value}")),
+            }
+        }
+        _ => return Err(format!("Unknown option: {option}")),
+    }
+    Ok(())
+}
+
+fn parse_on_off(value: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("Expected on or off instead of: {value}")),
+    }
+}
+
 fn print_examples() {
     let mut small_limit = 100;
     let mut perf_limit = 10000000;